@@ -0,0 +1,118 @@
+use std::io::Write;
+
+use image::RgbaImage;
+
+use crate::raster::{Rasterizer, RenderOptions, TrailMode};
+use crate::types::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    /// `.gif` is a gif, `.png` and `.apng` are animated pngs.
+    pub fn from_path(path: &str) -> Option<AnimationFormat> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "gif" => Some(AnimationFormat::Gif),
+            "png" | "apng" => Some(AnimationFormat::Apng),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationOptions {
+    pub render: RenderOptions,
+    /// Frames per second, one frame per `MoveTimer` tick.
+    pub fps: u32,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> AnimationOptions {
+        // matches the default MoveTimer of 0.05 seconds
+        AnimationOptions{render: RenderOptions::default(), fps: 20}
+    }
+}
+
+/// The part 1 guard, or the one picked with `TrailMode::Guard`.
+fn walking_guard<'a>(guards: &'a AllGuards, options: &AnimationOptions) -> Result<&'a Guard, String> {
+    let wanted = match options.render.mode {
+        TrailMode::Guard(idx) => idx,
+        _ => 0,
+    };
+    guards.iter().find(|g| g.display_index == wanted).ok_or_else(|| format!("No guard {}", wanted))
+}
+
+/// Walks the guard chosen by `options.render.mode` along its trail, calling `f` with each frame.
+pub fn for_each_frame(
+    room: &Room,
+    guards: &AllGuards,
+    options: &AnimationOptions,
+    mut f: impl FnMut(&RgbaImage) -> Result<(), String>,
+) -> Result<(), String> {
+    let guard = walking_guard(guards, options)?;
+    let mut rasterizer = Rasterizer::new(options.render);
+    let mut canvas = rasterizer.render_room(room);
+    rasterizer.draw_loop_obstacle(&mut canvas, guard);
+    let mut walker = guard.clone();
+    walker.reset();
+    for step in 0..guard.trail.len() {
+        walker.trail_idx = step;
+        rasterizer.draw_trail_step(&mut canvas, guard, step);
+        let mut frame = canvas.clone();
+        rasterizer.draw_guard(&mut frame, &walker);
+        f(&frame)?;
+    }
+    Ok(())
+}
+
+fn frame_size(room: &Room, options: &AnimationOptions) -> (u32, u32) {
+    let cell = options.render.cell_size;
+    (room.len() as u32 * cell, room.height() as u32 * cell)
+}
+
+pub fn write_animation<W: Write>(
+    room: &Room,
+    guards: &AllGuards,
+    options: &AnimationOptions,
+    format: AnimationFormat,
+    out: W,
+) -> Result<(), String> {
+    let fps = options.fps.max(1);
+    match format {
+        AnimationFormat::Gif => {
+            let (width, height) = frame_size(room, options);
+            let (width, height) = (u16::try_from(width), u16::try_from(height));
+            let (Ok(width), Ok(height)) = (width, height) else {
+                return Err("Room is too large for a gif, try a smaller --cell-size".to_string());
+            };
+            let mut encoder = gif::Encoder::new(out, width, height, &[]).map_err(|e| e.to_string())?;
+            encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+            // gif delays are in hundredths of a second
+            let delay = (100 / fps).max(1) as u16;
+            for_each_frame(room, guards, options, |frame| {
+                let mut pixels = frame.as_raw().clone();
+                let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+                gif_frame.delay = delay;
+                encoder.write_frame(&gif_frame).map_err(|e| e.to_string())
+            })
+        }
+        AnimationFormat::Apng => {
+            let frames = walking_guard(guards, options)?.trail.len();
+            let (width, height) = frame_size(room, options);
+            let mut encoder = png::Encoder::new(out, width, height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_animated(frames.max(1) as u32, 0).map_err(|e| e.to_string())?;
+            encoder.set_frame_delay(1, fps.min(u16::MAX as u32) as u16).map_err(|e| e.to_string())?;
+            let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+            for_each_frame(room, guards, options, |frame| {
+                writer.write_image_data(frame.as_raw()).map_err(|e| e.to_string())
+            })?;
+            writer.finish().map_err(|e| e.to_string())
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::task::{Context, Poll};
 
 use bevy::{
@@ -9,6 +10,10 @@ use bevy_egui::EguiPlugin;
 
 use crate::types::*;
 use crate::camera::*;
+use crate::heatmap::*;
+use crate::minimap::*;
+use crate::inspector::*;
+use crate::compare::*;
 use crate::controls::*;
 
 pub fn run() {
@@ -18,34 +23,47 @@ pub fn run() {
         .init_state::<AppState>()
         .init_asset::<TextAsset>() // Register the custom asset type
         .init_asset_loader::<TextAssetLoader>() // Register the custom loader
+        .init_asset_loader::<RoomImageLoader>() // Pixel-art rooms, also produces a TextAsset
         .insert_resource(AllRooms::new())
         .insert_resource(StateInfo::new())
         .insert_resource(PendingText(String::new()))
         .insert_resource(CurrentError(String::new()))
+        .insert_resource(PendingExport(None))
+        .insert_resource(CurrentGlyphs(GlyphMap::default().name.to_string()))
+        .insert_resource(GeneratorSettings(Default::default()))
         .insert_resource(MoveTimer(Timer::from_seconds(0.05, TimerMode::Repeating)))
+        .insert_resource(Playback::default())
+        .insert_resource(Heatmap::default())
+        .insert_resource(CameraControl::default())
+        .insert_resource(Comparison::default())
         .add_systems(Startup,(setup_camera,setup_menu))
-        .add_systems(Update,menu)
+        .add_systems(Update,(menu,export_window))
         .add_systems(Update,handle_calc_tasks)
         .add_systems(OnEnter(AppState::InputScreen),add_examples)
         .add_systems(Update,(load_inputs,handle_input,load_examples).run_if(in_state(AppState::InputScreen)))
         .add_systems(OnExit(AppState::InputScreen),(spawn_calc_tasks).chain())
         .add_systems(OnEnter(AppState::Part1),(room_setup, guard_spawn).chain())
         .add_systems(Update,(
+            scrub_trails,
             render_trail,
             move_guard,
             update_camera,
         ).chain().run_if(in_state(AppState::Part1)))
         .add_systems(Update,(guard_controls,prog_update_system,resize_trails).run_if(in_state(AppState::Part1)))
+        .add_systems(Update,(heatmap_controls,apply_heatmap,pulse_cycles,cell_inspector).run_if(not(in_state(AppState::InputScreen))))
+        .add_systems(Update,(pan_zoom_camera,fit_camera,minimap).chain().run_if(not(in_state(AppState::InputScreen))))
         .add_systems(OnExit(AppState::Part1),cleanup_room)
         .add_systems(OnEnter(AppState::Part2),(room_setup, sort_guards, guard_spawn).chain())
         .add_systems(Update,(
+            scrub_trails,
             render_trail,
             move_guard,
             update_camera,
             cleanup_non_looping,
         ).chain().run_if(in_state(AppState::Part2)))
-        .add_systems(Update,(guard_controls,resize_trails).run_if(in_state(AppState::Part2)))
-        .add_systems(OnExit(AppState::Part2),cleanup_room)
+        .add_systems(Update,(guard_controls,resize_trails,select_obstacle).run_if(in_state(AppState::Part2)))
+        .add_systems(Update,(compare_controls,compare_camera,compare_overlay).chain().run_if(in_state(AppState::Part2)))
+        .add_systems(OnExit(AppState::Part2),(end_compare,cleanup_room))
         .run();
 }
 
@@ -55,17 +73,23 @@ pub fn load_inputs(
     input_text: Query<(Entity,&InputText)>,
 ) {
     for (ent, text) in &input_text {
-        match crate::part1and2::part1(text.0.clone()) {
-            Ok(mut new_room) => {
-                new_room.0.index = rooms.0.len();
-                rooms.push(new_room);
-            }
-            Err(err) => {
-                let final_msg = format!("Error: {}. Caused by input:\n{}", err, text.0.clone());
-                println!("{}", final_msg);
-                commands.spawn(ErrorBox(final_msg));
+        let mut errors = Vec::new();
+        for (i, section) in text.0.iter().enumerate() {
+            match section.part1() {
+                Ok(mut new_room) => {
+                    new_room.0.index = rooms.0.len();
+                    rooms.push(new_room);
+                }
+                Err(err) => {
+                    errors.push(format!("Error in room {}: {} Caused by input:\n{}", section.label(i), err, section.body));
+                }
             }
         }
+        if !errors.is_empty() {
+            let final_msg = errors.join("\n");
+            println!("{}", final_msg);
+            commands.spawn(ErrorBox(final_msg));
+        }
         commands.entity(ent).despawn();
     }
 }
@@ -103,11 +127,14 @@ fn spawn_calc_tasks(
 fn handle_calc_tasks(
     mut commands: Commands,
     mut tasks: Query<(Entity, &mut ComputeTrails)>,
+    mut generating: Query<(Entity, &mut GenerateRoom)>,
 ) {
-    for (entity, mut task) in &mut tasks {
+    let tasks = tasks.iter_mut().map(|(entity, task)| (entity, task.map_unchanged(|t| &mut t.0)));
+    let generating = generating.iter_mut().map(|(entity, task)| (entity, task.map_unchanged(|t| &mut t.0)));
+    for (entity, mut task) in tasks.chain(generating) {
         let waker = futures::task::noop_waker();
         let mut context = Context::from_waker(&waker);
-        if let Poll::Ready(mut commands_queue) = task.0.poll(&mut context) {
+        if let Poll::Ready(mut commands_queue) = task.poll(&mut context) {
             commands.append(&mut commands_queue);
             commands.entity(entity).despawn();
         }
@@ -248,9 +275,10 @@ fn move_guard(
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn resize_trails(
     stateinfo: Res<StateInfo>,
-    mut trailent: Query<(&TrailEntity, &mut Sprite), Without<Obstacle>>,
+    mut trailent: Query<(&TrailEntity, &mut Sprite), (Without<Obstacle>, Without<CycleEntry>)>,
     mut obstacles: Query<(&Obstacle, &mut Sprite)>
 ) {
     for (entobj, mut sprite) in trailent.iter_mut() {
@@ -271,57 +299,128 @@ fn resize_trails(
     };
 }
 
+fn trail_sprite(guard: &Guard, x: usize, y: usize, z: f32, focused: bool) -> impl Bundle {
+    let custom_size = if focused {
+        Some(Vec2::new(SCALED_CELL_SIZE/2., SCALED_CELL_SIZE/2.))
+    } else {
+        Some(Vec2::new(SCALED_CELL_SIZE/4., SCALED_CELL_SIZE/4.))
+    };
+    (
+        Sprite {
+            color: color_from_idx(guard.display_index),
+            custom_size,
+            ..default()
+        },
+        Transform::from_translation(Vec3::new(
+            x as f32 * SCALED_CELL_SIZE,
+            y as f32 * -SCALED_CELL_SIZE,
+            z,
+        )),
+        Visibility::default(),
+        TrailEntity::new(guard.trail_idx,guard.display_index),
+    )
+}
+
+/// Moves the guard one step and leaves a trail sprite behind. False once the walk is over.
+fn step_guard(commands: &mut Commands, guard: &mut Guard, state: &AppState, camera_target: usize) -> bool {
+    let focused = guard.display_index == camera_target;
+    if guard.trail_idx == 0 && *state == AppState::Part1 {
+        if let Some((_,(x,y))) = guard.trail.first() {
+            commands.spawn(trail_sprite(guard, *x, *y, (guard.display_index as f32)/10., focused));
+        }
+    }
+    let cycle_start = guard.cycle_start();
+    if let Some((_,(x,y))) = guard.advance() {
+        let mut sprite = commands.spawn(trail_sprite(guard, x, y, 1., focused));
+        if cycle_start.is_some_and(|start| guard.trail_idx >= start) {
+            sprite.insert(CycleTrail);
+        }
+        if cycle_start == Some(guard.trail_idx) {
+            // a white square under the trail, so the entry space gets a border
+            commands.spawn((
+                Sprite {
+                    color: Color::WHITE,
+                    custom_size: Some(Vec2::new(SCALED_CELL_SIZE*0.8, SCALED_CELL_SIZE*0.8)),
+                    ..default()
+                },
+                Transform::from_translation(Vec3::new(x as f32 * SCALED_CELL_SIZE, y as f32 * -SCALED_CELL_SIZE, 0.9)),
+                Visibility::default(),
+                TrailEntity::new(guard.trail_idx,guard.display_index),
+                CycleEntry {
+                    guard_index: guard.display_index,
+                    pos: (x,y),
+                    step: guard.trail_idx,
+                    cycle_len: guard.cycle_len().unwrap_or(0),
+                },
+            ));
+        }
+        true
+    } else {
+        false
+    }
+}
+
+/// Brightens and dims the trail sprites that are part of a cycle.
+fn pulse_cycles(
+    time: Res<Time>,
+    mut trails: Query<(&TrailEntity, &mut Sprite), With<CycleTrail>>,
+) {
+    let glow = (time.elapsed_secs() * 4.).sin() * 0.5 + 0.5;
+    for (trail, mut sprite) in trails.iter_mut() {
+        let base = color_from_idx(trail.guard_index).to_srgba();
+        sprite.color = base.mix(&Srgba::WHITE, 0.2 + glow * 0.4).into();
+    }
+}
+
+/// Jumps every guard to the trail index asked for by the timeline,
+/// removing the trail sprites past it when going backward.
+fn scrub_trails(
+    mut commands: Commands,
+    mut playback: ResMut<Playback>,
+    state: Res<State<AppState>>,
+    stateinfo: Res<StateInfo>,
+    mut guards: Query<&mut Guard>,
+    trails: Query<(Entity, &TrailEntity)>,
+) {
+    let Some(target) = playback.seek.take() else { return; };
+    // where each guard that went backward ended up, so the trail sprites are only walked once
+    let mut rewound = HashMap::new();
+    for mut guard in guards.iter_mut() {
+        let target = target.min(guard.trail.len());
+        if guard.trail_idx > target {
+            while guard.trail_idx > target && guard.retreat().is_some() {}
+            rewound.insert(guard.display_index, guard.trail_idx);
+        }
+        while guard.trail_idx < target && step_guard(&mut commands, &mut guard, state.get(), stateinfo.camera_target) {}
+    }
+    if rewound.is_empty() { return; }
+    for (entity, trail) in trails.iter() {
+        let Some(trail_idx) = rewound.get(&trail.guard_index) else { continue; };
+        // step 0 is drawn again by step_guard when playback restarts
+        if trail.index > *trail_idx || *trail_idx == 0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn render_trail(
     mut commands: Commands,
     time: Res<Time>,
     mut timer: ResMut<MoveTimer>,
+    playback: Res<Playback>,
     state: Res<State<AppState>>,
     stateinfo: Res<StateInfo>,
     mut guards: Query<&mut Guard>,
     mut loopquery: Query<(&mut Text, &mut LoopBoard)>,
 ) {
+    if playback.paused { return; }
     if timer.0.tick(time.delta()).just_finished() {
         for mut guard in guards.iter_mut() {
-            let custom_size = if guard.display_index == stateinfo.camera_target {
-                Some(Vec2::new(SCALED_CELL_SIZE/2., SCALED_CELL_SIZE/2.))
-            } else {
-                Some(Vec2::new(SCALED_CELL_SIZE/4., SCALED_CELL_SIZE/4.))
-            };
-            let color = color_from_idx(guard.display_index);
-            if guard.trail_idx == 0 && *state.get() == AppState::Part1 {
-                if let Some((_,(x,y))) = guard.trail.first() {
-                    commands.spawn((
-                        Sprite {
-                            color,
-                            custom_size,
-                            ..default()
-                        },
-                        Transform::from_translation(Vec3::new(
-                            *x as f32 * SCALED_CELL_SIZE,
-                            *y as f32 * -SCALED_CELL_SIZE,
-                            (guard.display_index as f32)/10.,
-                        )),
-                        Visibility::default(),
-                        TrailEntity::new(guard.trail_idx,guard.display_index),
-                    ));
-                }
+            if step_guard(&mut commands, &mut guard, state.get(), stateinfo.camera_target) {
+                continue;
             }
-            if let Some((_,(x,y))) = guard.advance() {
-                commands.spawn((
-                    Sprite {
-                        color,
-                        custom_size,
-                        ..default()
-                    },
-                    Transform::from_translation(Vec3::new(
-                        x as f32 * SCALED_CELL_SIZE,
-                        y as f32 * -SCALED_CELL_SIZE,
-                        1.,
-                    )),
-                    Visibility::default(),
-                    TrailEntity::new(guard.trail_idx,guard.display_index),
-                ));
-            } else if *state.get() == AppState::Part2 {
+            if *state.get() == AppState::Part2 {
                 if guard.is_loop {
                     if !guard.counted {
                         guard.counted = true;
@@ -365,14 +464,23 @@ fn cleanup_non_looping(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn cleanup_room(
     mut commands: Commands,
     items: Query<Entity, With<Space>>,
     guard: Query<Entity, With<Guard>>,
     trail: Query<Entity, With<TrailEntity>>,
     obstacles: Query<Entity, With<Obstacle>>,
+    mut playback: ResMut<Playback>,
+    mut camera_control: ResMut<CameraControl>,
+    mut projection: Query<&mut OrthographicProjection, With<Camera2d>>,
     mut loopquery: Query<(&mut Text, &mut LoopBoard, &mut Visibility)>,
 ) {
+    *playback = Playback::default();
+    *camera_control = CameraControl::default();
+    for mut projection in projection.iter_mut() {
+        projection.scale = 1.;
+    }
     for entity in items.iter() {
         commands.entity(entity).despawn_recursive();
     }
@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::Instant;
+
+/// One room of one file. Files that can't be read get a single row with the error.
+#[derive(Debug, Clone)]
+pub struct BatchRow {
+    pub file: String,
+    pub room: String,
+    pub width: usize,
+    pub height: usize,
+    pub part1: Option<usize>,
+    pub part2: Option<usize>,
+    pub secs: f64,
+    /// Read or parse failures, and answers that don't match the bundle header.
+    pub errors: Vec<String>,
+}
+
+pub const CSV_HEADER: &str = "file,room,width,height,part1,part2,secs,errors";
+
+/// Room files in `dir`, the same kinds `read_sections` takes, sorted by name.
+pub fn room_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut files: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| p.extension().and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("txt") || e.eq_ignore_ascii_case("png")))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Every room in one file, with part 2 spread over `threads` threads.
+pub fn solve_file(path: &Path, threads: usize) -> Vec<BatchRow> {
+    let file = path.to_string_lossy().to_string();
+    let failed = |err: String| vec![BatchRow {
+        file: file.clone(), room: String::new(), width: 0, height: 0,
+        part1: None, part2: None, secs: 0., errors: vec![err],
+    }];
+    let sections = match crate::cli::read_sections(&file) {
+        Ok(sections) => sections,
+        Err(err) => return failed(err),
+    };
+    sections.iter().enumerate().map(|(i, section)| {
+        let start = Instant::now();
+        let solved = crate::cli::solve_section_on(section, threads);
+        let secs = start.elapsed().as_secs_f64();
+        let mut row = BatchRow {
+            file: file.clone(), room: section.label(i), width: 0, height: 0,
+            part1: None, part2: None, secs, errors: Vec::new(),
+        };
+        match solved {
+            Ok((room, guards)) => {
+                let (part1, part2) = (room.visited, guards.loop_count());
+                row.width = room.len();
+                row.height = room.height();
+                row.part1 = Some(part1);
+                row.part2 = Some(part2);
+                if let Some(expected) = room.expected_part1.filter(|e| *e != part1) {
+                    row.errors.push(format!("part 1 expected {}", expected));
+                }
+                if let Some(expected) = room.expected_part2.filter(|e| *e != part2) {
+                    row.errors.push(format!("part 2 expected {}", expected));
+                }
+            }
+            Err(err) => row.errors.push(err),
+        }
+        row
+    }).collect()
+}
+
+/// Solves the files on `jobs` threads. `progress` hears about each file as it finishes,
+/// with how many are done so far. Rows come back in file order.
+pub fn solve_files(files: &[PathBuf], jobs: usize, mut progress: impl FnMut(usize, &Path, &[BatchRow])) -> Vec<BatchRow> {
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let mut results: Vec<Option<Vec<BatchRow>>> = vec![None; files.len()];
+    let workers = jobs.clamp(1, files.len().max(1));
+    // threads left over when there are fewer files than jobs go to part 2, so the total stays at `jobs`
+    let part2_threads = (jobs / workers).max(1);
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(path) = files.get(i) else { break; };
+                    if sender.send((i, solve_file(path, part2_threads))).is_err() { break; }
+                }
+            });
+        }
+        drop(sender);
+        for (done, (i, rows)) in receiver.iter().enumerate() {
+            progress(done + 1, &files[i], &rows);
+            results[i] = Some(rows);
+        }
+    });
+    results.into_iter().flatten().flatten().collect()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn to_csv(rows: &[BatchRow]) -> String {
+    let mut out = String::from(CSV_HEADER);
+    out.push('\n');
+    for row in rows {
+        let opt = |v: Option<usize>| v.map(|v| v.to_string()).unwrap_or_default();
+        out.push_str(&format!("{},{},{},{},{},{},{:.6},{}\n",
+            csv_field(&row.file), csv_field(&row.room), row.width, row.height,
+            opt(row.part1), opt(row.part2), row.secs, csv_field(&row.errors.join("; "))));
+    }
+    out
+}
+
+pub fn to_table(rows: &[BatchRow]) -> String {
+    let file_w = rows.iter().map(|r| r.file.len()).max().unwrap_or(0).max(4);
+    let room_w = rows.iter().map(|r| r.room.len()).max().unwrap_or(0).max(4);
+    let mut out = format!("{:<file_w$} {:<room_w$} {:>9} {:>7} {:>7} {:>10}  errors\n",
+        "file", "room", "size", "part1", "part2", "time ms");
+    for row in rows {
+        let opt = |v: Option<usize>| v.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+        // errors can span lines, keep the table to one line per room
+        let errors = row.errors.join("; ").replace('\n', " ");
+        out.push_str(&format!("{:<file_w$} {:<room_w$} {:>9} {:>7} {:>7} {:>10.2}  {}\n",
+            row.file, row.room, format!("{}x{}", row.width, row.height),
+            opt(row.part1), opt(row.part2), row.secs * 1000., errors));
+    }
+    let failed = rows.iter().filter(|r| !r.errors.is_empty()).count();
+    // an empty f64 sum is -0.0, which would print as -0.00
+    out.push_str(&format!("{} rooms, {} with errors, {:.2} ms solving\n",
+        rows.len(), failed, rows.iter().map(|r| r.secs).fold(0., |a, b| a + b) * 1000.));
+    out
+}
@@ -0,0 +1,152 @@
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::bundle::{split_bundle, RoomSection};
+use crate::generator::{GeneratorOptions, InitialPath};
+
+/// The text examples that ship with the app.
+const EXAMPLES: [(&str, &str); 4] = [
+    ("input1", include_str!("examples/input1.txt")),
+    ("input2", include_str!("examples/input2.txt")),
+    ("input3", include_str!("examples/input3.txt")),
+    ("input4", include_str!("examples/input4.txt")),
+];
+
+/// A field of `/proc/self/status`, in bytes.
+fn proc_status_bytes(field: &str) -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with(field))?;
+    let kib: usize = line[field.len()..].trim_start_matches(':').split_whitespace().next()?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Starts a new resident memory high-water mark and returns the resident size right now.
+/// Only Linux reports these, elsewhere there's no memory figure.
+fn reset_peak() -> Option<usize> {
+    std::fs::write("/proc/self/clear_refs", "5").ok()?;
+    proc_status_bytes("VmRSS")
+}
+
+fn peak() -> Option<usize> {
+    proc_status_bytes("VmHWM")
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchCase {
+    pub name: String,
+    pub width: usize,
+    pub height: usize,
+    pub part1: usize,
+    pub part2: usize,
+    pub candidates: usize,
+    pub part1_secs: f64,
+    /// Part 2 runs on one thread, so this and the mean compare across machines.
+    pub part2_secs: f64,
+    /// Part 2 time divided by the number of candidate obstacles.
+    pub mean_candidate_secs: f64,
+    pub total_secs: f64,
+    /// Trail entries of every guard, part 1 and part 2.
+    pub steps: usize,
+    /// Resident memory growth over the case, where the OS reports it.
+    pub peak_memory_bytes: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    pub cases: Vec<BenchCase>,
+    pub total_secs: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BenchOptions {
+    /// Generated rooms double in size from 16x16 up to this.
+    pub max_size: usize,
+    pub seed: u64,
+}
+
+impl Default for BenchOptions {
+    fn default() -> BenchOptions {
+        BenchOptions{max_size: 128, seed: 0}
+    }
+}
+
+/// The rooms that get timed, examples first.
+pub fn bench_rooms(options: &BenchOptions) -> Result<Vec<RoomSection>, String> {
+    let mut rooms = Vec::new();
+    for (name, text) in EXAMPLES {
+        for mut section in split_bundle(text) {
+            section.name.get_or_insert_with(|| name.to_string());
+            rooms.push(section);
+        }
+    }
+    let mut size = 16;
+    while size <= options.max_size {
+        let generator = GeneratorOptions {
+            width: size,
+            height: size,
+            seed: options.seed,
+            density: 0.05,
+            initial_path: InitialPath::Exits,
+            // long enough walks that bigger rooms mean more work
+            part1_at_least: Some(size * 2),
+            ..Default::default()
+        };
+        rooms.push(crate::generator::generate(&generator)?.0);
+        size *= 2;
+    }
+    Ok(rooms)
+}
+
+pub fn bench_case(section: &RoomSection) -> Result<BenchCase, String> {
+    let baseline = reset_peak();
+    let start = Instant::now();
+    let (room, mut guards) = section.part1()?;
+    let part1_secs = start.elapsed().as_secs_f64();
+    let start = Instant::now();
+    crate::part1and2::part2_all(&room, &mut guards, 1);
+    let part2_secs = start.elapsed().as_secs_f64();
+    let peak_memory_bytes = baseline.zip(peak()).map(|(baseline, peak)| peak.saturating_sub(baseline));
+    let candidates = room.to_check.len();
+    Ok(BenchCase {
+        name: room.label(),
+        width: room.len(),
+        height: room.height(),
+        part1: room.visited,
+        part2: guards.loop_count(),
+        candidates,
+        part1_secs,
+        part2_secs,
+        mean_candidate_secs: if candidates == 0 { 0. } else { part2_secs / candidates as f64 },
+        total_secs: part1_secs + part2_secs,
+        steps: guards.iter().map(|g| g.trail.len()).sum(),
+        peak_memory_bytes,
+    })
+}
+
+pub fn run_bench(options: &BenchOptions) -> Result<BenchReport, String> {
+    let cases = bench_rooms(options)?.iter().map(bench_case).collect::<Result<Vec<_>, _>>()?;
+    Ok(BenchReport {
+        total_secs: cases.iter().map(|c| c.total_secs).sum(),
+        cases,
+    })
+}
+
+impl BenchReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+    pub fn to_table(&self) -> String {
+        let mut out = format!("{:<24} {:>9} {:>7} {:>7} {:>10} {:>10} {:>12} {:>10} {:>10} {:>10}\n",
+            "room", "size", "part1", "part2", "part1 ms", "part2 ms", "mean cand us", "total ms", "steps", "peak KiB");
+        for case in &self.cases {
+            out.push_str(&format!("{:<24} {:>9} {:>7} {:>7} {:>10.2} {:>10.2} {:>12.2} {:>10.2} {:>10} {:>10}\n",
+                case.name, format!("{}x{}", case.width, case.height), case.part1, case.part2,
+                case.part1_secs * 1000., case.part2_secs * 1000., case.mean_candidate_secs * 1e6,
+                case.total_secs * 1000., case.steps,
+                case.peak_memory_bytes.map(|b| (b / 1024).to_string()).unwrap_or_else(|| "-".to_string())));
+        }
+        out.push_str(&format!("total {:.2} ms, part 2 on one thread\n", self.total_secs * 1000.));
+        out
+    }
+}
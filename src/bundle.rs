@@ -0,0 +1,275 @@
+use std::fmt::{Display, Formatter};
+use crate::types::{AllGuards, GlyphMap, Room};
+
+/// Lines starting with this mark the beginning of a new room in a bundle.
+/// e.g. `=== Example 1 | source=AoC 2024 | glyphs=aoc | part1=41 | part2=6`
+pub const SECTION_MARKER: &str = "===";
+/// Lines starting with this inside a section are free-form notes about the room.
+pub const NOTE_MARKER: &str = "//";
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RoomSection {
+    pub name: Option<String>,
+    pub source: Option<String>,
+    pub notes: String,
+    pub glyphs: Option<String>,
+    pub expected_part1: Option<usize>,
+    pub expected_part2: Option<usize>,
+    pub body: String,
+    /// Header fields that couldn't be read, reported by `part1` instead of a room.
+    pub errors: Vec<String>,
+}
+
+impl RoomSection {
+    pub fn new(body: String) -> RoomSection {
+        RoomSection{name:None,source:None,notes:String::new(),glyphs:None,expected_part1:None,expected_part2:None,body,errors:Vec::new()}
+    }
+    /// Section that re-creates the room along with its name, source and notes.
+    pub fn from_room(room: &Room) -> RoomSection {
+        RoomSection {
+            name: room.name.clone(),
+            source: room.source.clone(),
+            notes: room.notes.clone(),
+            glyphs: None,
+            expected_part1: room.expected_part1,
+            expected_part2: room.expected_part2,
+            body: room.to_string(),
+            errors: Vec::new(),
+        }
+    }
+    pub fn copy_info(&self, room: &mut Room) {
+        room.name = self.name.clone();
+        room.source = self.source.clone();
+        room.notes = self.notes.clone();
+        room.expected_part1 = self.expected_part1;
+        room.expected_part2 = self.expected_part2;
+    }
+    fn from_header(header: &str) -> RoomSection {
+        let mut section = RoomSection::new(String::new());
+        let answer = |key: &str, val: &str, errors: &mut Vec<String>| match val.parse() {
+            Ok(answer) => Some(answer),
+            Err(_) => {
+                errors.push(format!("{}={} is not a number", key, val));
+                None
+            }
+        };
+        for (i, field) in header.split('|').map(|f|f.trim()).enumerate() {
+            match field.split_once('=').map(|(key,val)| (key.trim(), val.trim())) {
+                Some(("source",val)) => section.source = Some(val.to_string()),
+                Some(("glyphs",val)) => section.glyphs = Some(val.to_string()),
+                Some(("part1",val)) => section.expected_part1 = answer("part1", val, &mut section.errors),
+                Some(("part2",val)) => section.expected_part2 = answer("part2", val, &mut section.errors),
+                _ if i == 0 => if !field.is_empty() { section.name = Some(field.to_string()) },
+                Some((key,_)) => section.errors.push(format!("unknown header field \"{}\"", key)),
+                None if field.is_empty() => {},
+                None => section.errors.push(format!("header field \"{}\" has no =", field)),
+            }
+        }
+        section
+    }
+    /// Runs part 1 on the body, with the name, notes and expected answers copied onto the room.
+    pub fn part1(&self) -> Result<(Room,AllGuards), String> {
+        if !self.errors.is_empty() {
+            return Err(format!("Bad header: {}.", self.errors.join(", ")));
+        }
+        let glyph_name = self.glyphs.as_deref().unwrap_or(GlyphMap::default().name);
+        let glyphs = GlyphMap::from_name(glyph_name).ok_or_else(|| {
+            format!("Unknown glyphs \"{}\", expected one of {}.", glyph_name, GlyphMap::PRESETS.join(", "))
+        })?;
+        let (mut room, guards) = crate::part1and2::part1(self.body.clone(), &glyphs).map_err(|e| e.to_string())?;
+        self.copy_info(&mut room);
+        Ok((room, guards))
+    }
+    /// Name used when reporting errors, falls back to the position in the bundle.
+    pub fn label(&self, idx: usize) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("#{}", idx),
+        }
+    }
+}
+
+impl Display for RoomSection {
+    fn fmt(&self, fmt:&mut Formatter) -> std::fmt::Result {
+        let mut header = Vec::new();
+        if let Some(name) = &self.name { header.push(name.clone()); }
+        if let Some(source) = &self.source { header.push(format!("source={}", source)); }
+        if let Some(glyphs) = &self.glyphs { header.push(format!("glyphs={}", glyphs)); }
+        if let Some(p1) = self.expected_part1 { header.push(format!("part1={}", p1)); }
+        if let Some(p2) = self.expected_part2 { header.push(format!("part2={}", p2)); }
+        if !header.is_empty() {
+            if self.name.is_none() { header.insert(0, String::new()); }
+            writeln!(fmt, "{} {}", SECTION_MARKER, header.join(" | "))?;
+        }
+        for line in self.notes.lines() {
+            writeln!(fmt, "{} {}", NOTE_MARKER, line)?;
+        }
+        fmt.write_str(&self.body)
+    }
+}
+
+/// Splits a bundle into its rooms.
+/// Text without any header lines is treated as a single room.
+pub fn split_bundle(input: &str) -> Vec<RoomSection> {
+    let mut sections = Vec::new();
+    let mut current = RoomSection::new(String::new());
+    let mut body = Vec::new();
+    let mut seen_header = false;
+    for line in input.lines() {
+        if let Some(header) = line.strip_prefix(SECTION_MARKER) {
+            let previous = std::mem::replace(&mut current, RoomSection::from_header(header.trim()));
+            // notes above the first header describe the bundle, keep them with the first room
+            if !seen_header && trim_blank(&body).is_empty() {
+                current.notes = previous.notes;
+            } else {
+                push_section(&mut sections, previous, &body);
+            }
+            body.clear();
+            seen_header = true;
+        } else if let Some(note) = line.strip_prefix(NOTE_MARKER) {
+            if !current.notes.is_empty() { current.notes.push('\n'); }
+            current.notes.push_str(note.strip_prefix(' ').unwrap_or(note));
+        } else {
+            body.push(line);
+        }
+    }
+    push_section(&mut sections, current, &body);
+    // keep empty input around so it still reports an error
+    if sections.is_empty() {
+        sections.push(RoomSection::new(String::new()));
+    }
+    sections
+}
+
+/// Fills in what a file's sections don't say about themselves: the file as the source,
+/// and the file name for unnamed rooms, numbered when the file holds more than one.
+pub fn name_from_file(sections: &mut [RoomSection], path: &std::path::Path) {
+    let stem = path.file_stem().map(|n|n.to_string_lossy().to_string()).unwrap_or_default();
+    let many = sections.len() > 1;
+    for (i, section) in sections.iter_mut().enumerate() {
+        section.source.get_or_insert_with(|| path.to_string_lossy().to_string());
+        if section.name.is_none() && !stem.is_empty() {
+            section.name = Some(if many { format!("{} #{}", stem, i) } else { stem.clone() });
+        }
+    }
+}
+
+fn push_section(sections: &mut Vec<RoomSection>, mut section: RoomSection, body: &[&str]) {
+    let body = trim_blank(body);
+    // skip the blank space before the first header
+    if body.is_empty() && section == RoomSection::new(String::new()) { return; }
+    section.body = body.join("\n");
+    sections.push(section);
+}
+
+fn trim_blank<'a, 'b>(lines: &'a [&'b str]) -> &'a [&'b str] {
+    let start = lines.iter().position(|l|!l.trim().is_empty()).unwrap_or(lines.len());
+    let end = lines.iter().rposition(|l|!l.trim().is_empty()).map(|i|i+1).unwrap_or(start);
+    &lines[start..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLE: &str = "\
+=== Example 1 | source=AoC 2024 | glyphs=aoc | part1=41 | part2=6
+// the puzzle's example
+// second note line
+....#.....
+.........#
+
+=== | source=made up | part2=3
+#...
+.^..
+";
+
+    #[test]
+    fn text_without_headers_is_one_room() {
+        let sections = split_bundle("\n#..\n.^.\n\n");
+        assert_eq!(sections, vec![RoomSection::new("#..\n.^.".to_string())]);
+    }
+
+    #[test]
+    fn empty_text_is_one_empty_room() {
+        assert_eq!(split_bundle(""), vec![RoomSection::new(String::new())]);
+    }
+
+    #[test]
+    fn headers_and_notes_are_read() {
+        let sections = split_bundle(BUNDLE);
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0], RoomSection {
+            name: Some("Example 1".to_string()),
+            source: Some("AoC 2024".to_string()),
+            notes: "the puzzle's example\nsecond note line".to_string(),
+            glyphs: Some("aoc".to_string()),
+            expected_part1: Some(41),
+            expected_part2: Some(6),
+            body: "....#.....\n.........#".to_string(),
+            errors: Vec::new(),
+        });
+        assert_eq!(sections[1].name, None);
+        assert_eq!(sections[1].source.as_deref(), Some("made up"));
+        assert_eq!(sections[1].expected_part2, Some(3));
+        assert_eq!(sections[1].label(1), "#1");
+        assert_eq!(sections[1].body, "#...\n.^..");
+    }
+
+    #[test]
+    fn notes_above_the_first_header_go_to_the_first_room() {
+        let sections = split_bundle("// my rooms\n\n=== A\n// about A\n^..\n=== B\n.^.\n");
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].name.as_deref(), Some("A"));
+        assert_eq!(sections[0].notes, "my rooms\nabout A");
+        assert_eq!(sections[0].body, "^..");
+        assert_eq!(sections[1].notes, "");
+        assert!(sections[0].part1().is_ok());
+    }
+
+    #[test]
+    fn bad_header_fields_are_errors() {
+        let sections = split_bundle("=== A | part1=41x | part2 = 6 | glpyhs=aoc | oops\n^..\n");
+        assert_eq!(sections[0].errors, vec![
+            "part1=41x is not a number".to_string(),
+            "unknown header field \"glpyhs\"".to_string(),
+            "header field \"oops\" has no =".to_string(),
+        ]);
+        assert_eq!(sections[0].expected_part2, Some(6));
+        let err = sections[0].part1().unwrap_err();
+        assert!(err.contains("part1=41x is not a number"), "{}", err);
+    }
+
+    #[test]
+    fn header_round_trips() {
+        for section in split_bundle(BUNDLE) {
+            assert_eq!(split_bundle(&section.to_string()), vec![section]);
+        }
+    }
+
+    #[test]
+    fn unnamed_rooms_take_the_file_name() {
+        let mut single = split_bundle("#..\n.^.");
+        name_from_file(&mut single, std::path::Path::new("rooms/input1.txt"));
+        assert_eq!(single[0].name.as_deref(), Some("input1"));
+        assert_eq!(single[0].source.as_deref(), Some("rooms/input1.txt"));
+        let mut bundle = split_bundle(BUNDLE);
+        name_from_file(&mut bundle, std::path::Path::new("bundle.txt"));
+        assert_eq!(bundle[0].name.as_deref(), Some("Example 1"));
+        assert_eq!(bundle[0].source.as_deref(), Some("AoC 2024"));
+        assert_eq!(bundle[1].name.as_deref(), Some("bundle #1"));
+    }
+
+    #[test]
+    fn room_info_survives_from_room() {
+        let mut section = split_bundle(BUNDLE).remove(0);
+        section.body = include_str!("examples/input1.txt").trim_end().to_string();
+        let (room, _) = section.part1().unwrap_or_else(|e| panic!("{}", e));
+        let copy = RoomSection::from_room(&room);
+        assert_eq!(copy.name, section.name);
+        assert_eq!(copy.source, section.source);
+        assert_eq!(copy.notes, section.notes);
+        assert_eq!(copy.expected_part1, Some(41));
+        assert_eq!(copy.body.trim_end(), section.body);
+    }
+}
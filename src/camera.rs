@@ -1,19 +1,33 @@
 use bevy::prelude::*;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
 use crate::types::*;
 
 pub fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2d);
+    // the comparison camera comes and goes, the menu stays on this one
+    commands.spawn((Camera2d, IsDefaultUiCamera));
 }
 
+#[allow(clippy::type_complexity)]
 pub fn update_camera(
-    mut camera: Query<&mut Transform, (With<Camera2d>, Without<Guard>)>,
+    mut camera: Query<(&mut Transform, &OrthographicProjection), (With<Camera2d>, Without<Guard>, Without<CompareCamera>)>,
     stateinfo: Res<StateInfo>,
+    control: Res<CameraControl>,
+    comparison: Res<Comparison>,
+    windows: Query<&Window, With<PrimaryWindow>>,
     guards: Query<(&Transform, &Guard), Without<Camera2d>>,
     time: Res<Time>,
 ) {
-    let Ok(mut camera) = camera.get_single_mut() else {
+    if !control.follow { return; }
+    let Ok((mut camera, projection)) = camera.get_single_mut() else {
         return;
     };
+    // while comparing the right half is covered, so center the guard in the left half
+    let offset = match windows.get_single() {
+        Ok(window) if comparison.enabled => window.width() / 4. * projection.scale,
+        _ => 0.,
+    };
 
     let mut guard = None;
 
@@ -25,7 +39,7 @@ pub fn update_camera(
 
     if let Some(g) = guard {
         let Vec3{ x, y, .. } = g.translation;
-        let direction = Vec3::new(x, y, camera.translation.z);
+        let direction = Vec3::new(x + offset, y, camera.translation.z);
 
         // Applies a smooth effect to camera movement using stable interpolation
         // between the camera position and the player position on the x and y axes.
@@ -34,3 +48,85 @@ pub fn update_camera(
             .smooth_nudge(&direction, CAMERA_DECAY_RATE, time.delta_secs());
     }
 }
+
+/// Room space under the mouse, if the mouse is over the room.
+/// With more than one camera, the topmost one whose viewport has the mouse in it is used.
+pub fn cursor_cell<'a>(
+    window: &Window,
+    cameras: impl IntoIterator<Item = (&'a Camera, &'a GlobalTransform)>,
+    room: &Room,
+) -> Option<(usize, usize)> {
+    let cursor = window.cursor_position()?;
+    let (camera, camera_transform) = cameras.into_iter()
+        .filter(|(camera, _)| camera.logical_viewport_rect().is_some_and(|rect| rect.contains(cursor)))
+        .max_by_key(|(camera, _)| camera.order)?;
+    let world = camera.viewport_to_world_2d(camera_transform, cursor).ok()?;
+    // spaces are centered on their position, and y goes down the room
+    let (x, y) = ((world.x / SCALED_CELL_SIZE).round(), (-world.y / SCALED_CELL_SIZE).round());
+    if x < 0. || y < 0. { return None; }
+    let (x, y) = (x as usize, y as usize);
+    (x < room.len() && y < room.height()).then_some((x, y))
+}
+
+/// Drag to pan, which stops following the guard, and scroll to zoom around the mouse.
+#[allow(clippy::type_complexity)]
+pub fn pan_zoom_camera(
+    mut contexts: EguiContexts,
+    mut control: ResMut<CameraControl>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut wheel: EventReader<MouseWheel>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection), (With<Camera2d>, Without<CompareCamera>)>,
+    mut last_cursor: Local<Option<Vec2>>,
+) {
+    let (Ok(window), Ok((mut transform, mut projection))) = (windows.get_single(), camera.get_single_mut()) else { return; };
+    let over_ui = contexts.ctx_mut().is_pointer_over_area();
+    let cursor = window.cursor_position();
+    if buttons.pressed(MouseButton::Left) && !buttons.just_pressed(MouseButton::Left) {
+        if let (Some(now), Some(before)) = (cursor, *last_cursor) {
+            let delta = now - before;
+            if delta != Vec2::ZERO {
+                control.follow = false;
+                transform.translation.x -= delta.x * projection.scale;
+                transform.translation.y += delta.y * projection.scale;
+            }
+        }
+    }
+    // only drags that start on the room move the camera
+    *last_cursor = if buttons.pressed(MouseButton::Left) && (last_cursor.is_some() || !over_ui) { cursor } else { None };
+    let scrolled: f32 = wheel.read().map(|e| match e.unit {
+        MouseScrollUnit::Line => e.y,
+        MouseScrollUnit::Pixel => e.y / 100.,
+    }).sum();
+    if scrolled == 0. || over_ui { return; }
+    let old_scale = projection.scale;
+    projection.scale = (old_scale * 1.1f32.powf(-scrolled)).clamp(0.05, 50.);
+    if let Some(cursor) = cursor {
+        // keep the point under the mouse where it is
+        let offset = cursor - Vec2::new(window.width(), window.height()) / 2.;
+        transform.translation.x += offset.x * (old_scale - projection.scale);
+        transform.translation.y -= offset.y * (old_scale - projection.scale);
+    }
+}
+
+/// Centers the camera on the room and zooms so all of it fits, when asked to.
+#[allow(clippy::type_complexity)]
+pub fn fit_camera(
+    mut control: ResMut<CameraControl>,
+    rooms: Res<AllRooms>,
+    stateinfo: Res<StateInfo>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection), (With<Camera2d>, Without<CompareCamera>)>,
+) {
+    if !control.fit { return; }
+    control.fit = false;
+    let Some((room, _)) = rooms.get_room(stateinfo.room_idx) else { return; };
+    let (Ok(window), Ok((mut transform, mut projection))) = (windows.get_single(), camera.get_single_mut()) else { return; };
+    control.follow = false;
+    let (width, height) = (room.len() as f32, room.height() as f32);
+    transform.translation.x = (width - 1.) * SCALED_CELL_SIZE / 2.;
+    transform.translation.y = -(height - 1.) * SCALED_CELL_SIZE / 2.;
+    // a little margin around the edges
+    let scale = (width * SCALED_CELL_SIZE / window.width()).max(height * SCALED_CELL_SIZE / window.height()) * 1.05;
+    projection.scale = scale.max(0.05);
+}
@@ -0,0 +1,531 @@
+use std::io::Read;
+use std::path::Path;
+
+use crate::bundle::*;
+use crate::report::Report;
+use crate::types::*;
+
+const USAGE: &str = "usage: day6vis [command]
+With no command the visualization starts.
+commands:
+    solve <file|-> [--json]                 print the part 1 and part 2 answers
+    report <file|-> [--json]                markdown or json summary of each room
+    trails <file|-> [--room r] [--json]     every guard's trail as csv or json
+    path <file|-> [--room r] [--guard g]    draw a trail in the puzzle's notation
+    render <file|-> --out room.png [--room r] [--part1|--loops|--guard g] [--cell-size px]
+                                            draw the room and trails to a png without a gpu
+    animate <file|-> --out walk.gif|walk.png [--room r] [--guard g] [--fps n] [--cell-size px]
+                                            gif or animated png of a guard's walk
+    svg <file|-> [--out room.svg] [--room r] [--part1|--loops|--guard g] [--cell-size px]
+                                            vector image of the room and trails
+    generate [--size WxH] [--density d] [--seed n] [--exits|--loops] [--part1-min n] [--part2-min n] [--bundle] [--out room.txt]
+                                            random room, plain or with a bundle header
+    bench [--max-size n] [--seed n] [--json]
+                                            time the solver on the examples and generated rooms
+    verify <expectations.toml>              solve each listed room and compare with the expected answers
+    batch <dir> [--out batch.csv] [--jobs n]
+                                            solve every room file in a folder, with a summary table and csv
+    tui <file> [--room r] [--fps n]         play the guard walks in the terminal";
+
+/// Runs a subcommand if one was given.
+/// Returns the exit code, or None to start the visualization instead.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    Some(match command.as_str() {
+        "solve" => solve(rest),
+        "report" => report(rest),
+        "trails" => trails(rest),
+        "path" => path(rest),
+        "render" => render(rest),
+        "animate" => animate(rest),
+        "svg" => svg(rest),
+        "generate" => generate(rest),
+        "bench" => bench(rest),
+        "verify" => verify(rest),
+        "batch" => batch(rest),
+        #[cfg(not(target_arch = "wasm32"))]
+        "tui" => tui(rest),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            0
+        }
+        _ => {
+            eprintln!("Unknown command {}\n{}", command, USAGE);
+            2
+        }
+    })
+}
+
+/// Reads a room file, a bundle, or a pixel-art png. `-` reads stdin.
+pub fn read_sections(path: &str) -> Result<Vec<RoomSection>, String> {
+    if path == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text).map_err(|e| format!("stdin: {}", e))?;
+        return Ok(split_bundle(&text));
+    }
+    let file = Path::new(path);
+    if file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png")) {
+        let bytes = std::fs::read(file).map_err(|e| format!("{}: {}", path, e))?;
+        return Ok(vec![crate::controls::section_from_png(&bytes, file)?]);
+    }
+    let text = std::fs::read_to_string(file).map_err(|e| format!("{}: {}", path, e))?;
+    let mut sections = split_bundle(&text);
+    name_from_file(&mut sections, file);
+    Ok(sections)
+}
+
+/// Part 1 and every part 2 candidate, without a window.
+pub fn solve_section(section: &RoomSection) -> Result<(Room, AllGuards), String> {
+    solve_section_on(section, crate::part1and2::available_threads())
+}
+
+/// `solve_section` with part 2 spread over `threads` threads.
+pub fn solve_section_on(section: &RoomSection, threads: usize) -> Result<(Room, AllGuards), String> {
+    let (room, mut guards) = section.part1()?;
+    crate::part1and2::part2_all(&room, &mut guards, threads);
+    Ok((room, guards))
+}
+
+/// First argument that isn't an option or an option's value.
+fn positional(args: &[String]) -> Option<&String> {
+    let mut skip = false;
+    for arg in args {
+        if skip {
+            skip = false;
+        } else if arg == "-" || !arg.starts_with("--") {
+            return Some(arg);
+        } else {
+            skip = VALUE_OPTIONS.contains(&arg.as_str());
+        }
+    }
+    None
+}
+
+/// Options that take a value, so `positional` can skip it.
+const VALUE_OPTIONS: [&str; 12] = [
+    "--room", "--guard", "--out", "--cell-size", "--fps",
+    "--size", "--density", "--seed", "--part1-min", "--part2-min", "--max-size", "--jobs",
+];
+
+fn option_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1)).map(|v| v.as_str())
+}
+
+/// Solves the room picked with `--room <index|name>`, or the first one in the file.
+fn solve_selected(args: &[String], usage: &str) -> Result<(Room, AllGuards), i32> {
+    let Some(path) = positional(args) else {
+        eprintln!("usage: {}", usage);
+        return Err(2);
+    };
+    let sections = read_sections(path).map_err(|err| {
+        eprintln!("{}", err);
+        1
+    })?;
+    let wanted = option_value(args, "--room").unwrap_or("0");
+    let Some((i, section)) = sections.iter().enumerate()
+        .find(|(i, s)| i.to_string() == wanted || s.name.as_deref() == Some(wanted)) else {
+        eprintln!("No room {} in {}", wanted, path);
+        return Err(1);
+    };
+    solve_section(section).map(|(mut room, guards)| {
+        room.index = i;
+        (room, guards)
+    }).map_err(|err| {
+        eprintln!("Error in room {}: {}", section.label(i), err);
+        1
+    })
+}
+
+fn trails(args: &[String]) -> i32 {
+    let (_, guards) = match solve_selected(args, "day6vis trails <file|-> [--room <index|name>] [--json]") {
+        Ok(solved) => solved,
+        Err(code) => return code,
+    };
+    let rows = crate::trails::trail_rows(&guards);
+    if args.iter().any(|a| a == "--json") {
+        println!("{}", crate::trails::to_json(&rows));
+    } else {
+        print!("{}", crate::trails::to_csv(&rows));
+    }
+    0
+}
+
+fn path(args: &[String]) -> i32 {
+    let (room, guards) = match solve_selected(args, "day6vis path <file|-> [--room <index|name>] [--guard <index>]") {
+        Ok(solved) => solved,
+        Err(code) => return code,
+    };
+    let guard_index = option_value(args, "--guard").and_then(|g| g.parse().ok()).unwrap_or(0);
+    match crate::trails::path_text(&room, &guards, guard_index) {
+        Some(text) => {
+            print!("{}", text);
+            0
+        }
+        None => {
+            eprintln!("No guard {}", guard_index);
+            1
+        }
+    }
+}
+
+/// Solves every room in the file. Rooms that fail to parse are reported on stderr
+/// and flagged with the returned bool.
+fn solve_every(args: &[String], usage: &str) -> Result<(Vec<(Room, AllGuards)>, bool), i32> {
+    let Some(path) = positional(args) else {
+        eprintln!("usage: {}", usage);
+        return Err(2);
+    };
+    let sections = read_sections(path).map_err(|err| {
+        eprintln!("{}", err);
+        1
+    })?;
+    let mut solved = Vec::new();
+    let mut failed = false;
+    for (i, section) in sections.iter().enumerate() {
+        match solve_section(section) {
+            Ok((mut room, guards)) => {
+                room.index = i;
+                solved.push((room, guards));
+            }
+            Err(err) => {
+                eprintln!("Error in room {}: {}", section.label(i), err);
+                failed = true;
+            }
+        }
+    }
+    Ok((solved, failed))
+}
+
+/// Prints one value as JSON, or an array when the file held several rooms.
+fn print_json<T: serde::Serialize>(items: &[T]) {
+    let out = match items {
+        [single] => serde_json::to_string_pretty(single),
+        _ => serde_json::to_string_pretty(items),
+    };
+    println!("{}", out.unwrap_or_default());
+}
+
+#[derive(serde::Serialize)]
+struct Answers {
+    name: String,
+    part1: usize,
+    part2: usize,
+}
+
+fn solve(args: &[String]) -> i32 {
+    let (solved, failed) = match solve_every(args, "day6vis solve <file|-> [--json]") {
+        Ok(solved) => solved,
+        Err(code) => return code,
+    };
+    let answers: Vec<Answers> = solved.iter().map(|(room, guards)| Answers {
+        name: room.label(),
+        part1: room.visited,
+        part2: guards.loop_count(),
+    }).collect();
+    if args.iter().any(|a| a == "--json") {
+        print_json(&answers);
+    } else {
+        for answer in &answers {
+            if answers.len() > 1 {
+                println!("Room {}", answer.name);
+            }
+            println!("Part 1: {}", answer.part1);
+            println!("Part 2: {}", answer.part2);
+        }
+    }
+    if failed { 1 } else { 0 }
+}
+
+/// `--part1`, `--loops` or `--guard <index>`, part 1 when none is given.
+fn trail_mode(args: &[String]) -> crate::raster::TrailMode {
+    use crate::raster::TrailMode;
+    if let Some(idx) = option_value(args, "--guard").and_then(|g| g.parse().ok()) {
+        TrailMode::Guard(idx)
+    } else if args.iter().any(|a| a == "--loops") {
+        TrailMode::AllLoops
+    } else {
+        TrailMode::Part1
+    }
+}
+
+/// None when --cell-size isn't a whole number of pixels above 0.
+fn render_options(args: &[String]) -> Option<crate::raster::RenderOptions> {
+    let mut options = crate::raster::RenderOptions{mode: trail_mode(args), ..Default::default()};
+    if let Some(size) = option_value(args, "--cell-size") {
+        options.cell_size = size.parse().ok().filter(|size| *size > 0)?;
+    }
+    Some(options)
+}
+
+fn render(args: &[String]) -> i32 {
+    let usage = "day6vis render <file|-> --out room.png [--room <index|name>] [--part1|--loops|--guard <index>] [--cell-size <px>]";
+    let (Some(out), Some(options)) = (option_value(args, "--out"), render_options(args)) else {
+        eprintln!("usage: {}", usage);
+        return 2;
+    };
+    let (room, guards) = match solve_selected(args, usage) {
+        Ok(solved) => solved,
+        Err(code) => return code,
+    };
+    let mut rasterizer = crate::raster::Rasterizer::new(options);
+    let image = rasterizer.render(&room, &guards);
+    match image.save_with_format(out, image::ImageFormat::Png) {
+        Ok(_) => 0,
+        Err(err) => {
+            eprintln!("{}: {}", out, err);
+            1
+        }
+    }
+}
+
+fn animate(args: &[String]) -> i32 {
+    use crate::animation::*;
+    let usage = "day6vis animate <file|-> --out walk.gif|walk.png [--room <index|name>] [--guard <index>] [--fps <n>] [--cell-size <px>]";
+    let Some((out, format)) = option_value(args, "--out").and_then(|out| Some((out, AnimationFormat::from_path(out)?))) else {
+        eprintln!("usage: {}", usage);
+        return 2;
+    };
+    let Some(render) = render_options(args) else {
+        eprintln!("usage: {}", usage);
+        return 2;
+    };
+    let (room, guards) = match solve_selected(args, usage) {
+        Ok(solved) => solved,
+        Err(code) => return code,
+    };
+    let mut options = AnimationOptions{render, ..Default::default()};
+    if let Some(fps) = option_value(args, "--fps").and_then(|f| f.parse().ok()) {
+        options.fps = fps;
+    }
+    let file = match std::fs::File::create(out) {
+        Ok(file) => std::io::BufWriter::new(file),
+        Err(err) => {
+            eprintln!("{}: {}", out, err);
+            return 1;
+        }
+    };
+    match write_animation(&room, &guards, &options, format, file) {
+        Ok(_) => 0,
+        Err(err) => {
+            eprintln!("{}: {}", out, err);
+            1
+        }
+    }
+}
+
+fn svg(args: &[String]) -> i32 {
+    let usage = "day6vis svg <file|-> [--out room.svg] [--room <index|name>] [--part1|--loops|--guard <index>] [--cell-size <px>]";
+    let Some(options) = render_options(args) else {
+        eprintln!("usage: {}", usage);
+        return 2;
+    };
+    let (room, guards) = match solve_selected(args, usage) {
+        Ok(solved) => solved,
+        Err(code) => return code,
+    };
+    let svg = crate::svg::room_svg(&room, &guards, &options);
+    match option_value(args, "--out") {
+        Some(out) => match std::fs::write(out, svg) {
+            Ok(_) => 0,
+            Err(err) => {
+                eprintln!("{}: {}", out, err);
+                1
+            }
+        },
+        None => {
+            print!("{}", svg);
+            0
+        }
+    }
+}
+
+/// Reads the generator options, `None` if one of them doesn't parse.
+fn generator_options(args: &[String]) -> Option<crate::generator::GeneratorOptions> {
+    use crate::generator::*;
+    let mut options = GeneratorOptions::default();
+    if let Some(size) = option_value(args, "--size") {
+        let (w, h) = size.split_once(['x', 'X'])?;
+        options.width = w.trim().parse().ok()?;
+        options.height = h.trim().parse().ok()?;
+    }
+    if let Some(density) = option_value(args, "--density") {
+        // NaN isn't in the range either
+        options.density = density.parse().ok().filter(|d: &f32| (0.0..=1.0).contains(d))?;
+    }
+    if let Some(seed) = option_value(args, "--seed") {
+        options.seed = seed.parse().ok()?;
+    }
+    if let Some(min) = option_value(args, "--part1-min") {
+        options.part1_at_least = Some(min.parse().ok()?);
+    }
+    if let Some(min) = option_value(args, "--part2-min") {
+        options.part2_at_least = Some(min.parse().ok()?);
+    }
+    if args.iter().any(|a| a == "--exits") {
+        options.initial_path = InitialPath::Exits;
+    } else if args.iter().any(|a| a == "--loops") {
+        options.initial_path = InitialPath::Loops;
+    }
+    Some(options)
+}
+
+fn generate(args: &[String]) -> i32 {
+    let usage = "day6vis generate [--size <W>x<H>] [--density <0-1>] [--seed <n>] [--exits|--loops] [--part1-min <n>] [--part2-min <n>] [--bundle] [--out room.txt]";
+    let Some(options) = generator_options(args) else {
+        eprintln!("usage: {}", usage);
+        return 2;
+    };
+    let section = match crate::generator::generate(&options) {
+        Ok((section, _)) => section,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+    let text = if args.iter().any(|a| a == "--bundle") { section.to_string() } else { section.body };
+    match option_value(args, "--out") {
+        Some(out) => match std::fs::write(out, text) {
+            Ok(_) => 0,
+            Err(err) => {
+                eprintln!("{}: {}", out, err);
+                1
+            }
+        },
+        None => {
+            print!("{}", text);
+            0
+        }
+    }
+}
+
+fn bench(args: &[String]) -> i32 {
+    use crate::bench::*;
+    let mut options = BenchOptions::default();
+    let max_size = option_value(args, "--max-size").map(|m| m.parse());
+    let seed = option_value(args, "--seed").map(|s| s.parse());
+    match (max_size, seed) {
+        (Some(Err(_)), _) | (_, Some(Err(_))) => {
+            eprintln!("usage: day6vis bench [--max-size <n>] [--seed <n>] [--json]");
+            return 2;
+        }
+        (max_size, seed) => {
+            if let Some(Ok(max_size)) = max_size { options.max_size = max_size; }
+            if let Some(Ok(seed)) = seed { options.seed = seed; }
+        }
+    }
+    match run_bench(&options) {
+        Ok(report) => {
+            if args.iter().any(|a| a == "--json") {
+                println!("{}", report.to_json());
+            } else {
+                print!("{}", report.to_table());
+            }
+            0
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+fn verify(args: &[String]) -> i32 {
+    use crate::verify::*;
+    let Some(path) = positional(args) else {
+        eprintln!("usage: day6vis verify <expectations.toml>");
+        return 2;
+    };
+    let expectations = match std::fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|t| parse_expectations(&t)) {
+        Ok(expectations) => expectations,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            return 1;
+        }
+    };
+    let base = Path::new(path).parent().unwrap_or(Path::new(""));
+    let outcomes = check_all(&expectations, base);
+    for outcome in &outcomes {
+        println!("{}", outcome.describe());
+    }
+    let failed = outcomes.iter().filter(|o| !o.passed()).count();
+    println!("{} checked, {} failed", outcomes.len(), failed);
+    if failed == 0 { 0 } else { 1 }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn tui(args: &[String]) -> i32 {
+    use crate::tui::*;
+    let usage = "day6vis tui <file> [--room <index|name>] [--fps <n>]";
+    if positional(args).is_some_and(|p| p == "-") {
+        // the keys are read from the terminal, so the room can't come from stdin
+        eprintln!("usage: {}", usage);
+        return 2;
+    }
+    let (room, guards) = match solve_selected(args, usage) {
+        Ok(solved) => solved,
+        Err(code) => return code,
+    };
+    let mut options = TuiOptions::default();
+    if let Some(fps) = option_value(args, "--fps").and_then(|f| f.parse::<f32>().ok()).filter(|f| *f > 0.) {
+        options.tick = 1. / fps;
+    }
+    match run_tui(&room, &guards, &options) {
+        Ok(_) => 0,
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+fn batch(args: &[String]) -> i32 {
+    use crate::batch::*;
+    let usage = "day6vis batch <dir> [--out batch.csv] [--jobs <n>]";
+    let Some(dir) = positional(args) else {
+        eprintln!("usage: {}", usage);
+        return 2;
+    };
+    let jobs = match option_value(args, "--jobs").map(|j| j.parse::<usize>()) {
+        Some(Ok(jobs)) => jobs,
+        Some(Err(_)) => {
+            eprintln!("usage: {}", usage);
+            return 2;
+        }
+        None => crate::part1and2::available_threads(),
+    };
+    let files = match room_files(Path::new(dir)) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 1;
+        }
+    };
+    let rows = solve_files(&files, jobs, |done, path, rows| {
+        let failed = rows.iter().filter(|r| !r.errors.is_empty()).count();
+        let secs: f64 = rows.iter().map(|r| r.secs).sum();
+        let status = if failed == 0 { "ok".to_string() } else { format!("{} failed", failed) };
+        eprintln!("[{}/{}] {} ({} rooms, {:.2} ms) {}", done, files.len(), path.display(), rows.len(), secs * 1000., status);
+    });
+    print!("{}", to_table(&rows));
+    let out = option_value(args, "--out").unwrap_or("batch.csv");
+    if let Err(err) = std::fs::write(out, to_csv(&rows)) {
+        eprintln!("{}: {}", out, err);
+        return 1;
+    }
+    if rows.iter().any(|r| !r.errors.is_empty()) { 1 } else { 0 }
+}
+
+fn report(args: &[String]) -> i32 {
+    let (solved, failed) = match solve_every(args, "day6vis report <file|-> [--json]") {
+        Ok(solved) => solved,
+        Err(code) => return code,
+    };
+    let reports: Vec<Report> = solved.iter().map(|(room, guards)| Report::new(room, guards)).collect();
+    if args.iter().any(|a| a == "--json") {
+        print_json(&reports);
+    } else {
+        let out: Vec<String> = reports.iter().map(|r| r.to_markdown()).collect();
+        print!("{}", out.join("\n"));
+    }
+    if failed { 1 } else { 0 }
+}
@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+use bevy::render::camera::Viewport;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::types::*;
+
+/// Last step a part 2 guard shares with the part 1 guard, where its extra obstacle turns it away.
+pub fn divergence_step(part1: &Guard, guard: &Guard) -> Option<usize> {
+    let shared = part1.trail.iter().zip(guard.trail.iter()).take_while(|(a, b)| a == b).count();
+    // a trail that never leaves the part 1 path has nothing to point at
+    (shared > 0 && shared < guard.trail.len()).then(|| shared - 1)
+}
+
+fn divergence_text(guards: &AllGuards, guard_index: usize) -> String {
+    let part1 = guards.iter().find(|g| g.display_index == 0);
+    let guard = guards.iter().find(|g| g.display_index == guard_index);
+    match (part1, guard) {
+        (Some(part1), Some(guard)) => match divergence_step(part1, guard) {
+            Some(step) => {
+                let (_, (x, y)) = guard.trail[step];
+                format!("Guard {} leaves the part 1 path at step {} ({},{})", guard_index, step, x, y)
+            }
+            None => format!("Guard {} never leaves the part 1 path", guard_index),
+        },
+        _ => format!("No guard {}", guard_index),
+    }
+}
+
+/// Window for turning the comparison on and picking the right hand guard.
+pub fn compare_controls(
+    mut contexts: EguiContexts,
+    mut comparison: ResMut<Comparison>,
+    stateinfo: Res<StateInfo>,
+    rooms: Res<AllRooms>,
+) {
+    let Some((_, guards)) = rooms.get_room(stateinfo.room_idx) else { return; };
+    let loops = guards.loop_indices();
+    let ctx = contexts.ctx_mut();
+    egui::Window::new("Compare").id(egui::Id::new("compare_window")).default_open(false).show(ctx, |ui| {
+        ui.checkbox(&mut comparison.enabled, "Split screen");
+        // guards that don't loop are despawned once they walk out, so only loops can be compared
+        if !loops.is_empty() && !loops.contains(&comparison.guard) {
+            comparison.guard = loops[0];
+        }
+        ui.horizontal(|ui| {
+            ui.label(format!("Left: guard {}", stateinfo.camera_target));
+            if loops.is_empty() {
+                ui.label("Right: no looping guards");
+                return;
+            }
+            egui::ComboBox::from_label("Right")
+                .selected_text(format!("guard {}", comparison.guard))
+                .show_ui(ui, |ui| {
+                    for index in &loops {
+                        ui.selectable_value(&mut comparison.guard, *index, format!("guard {}", index));
+                    }
+                });
+        });
+        if !loops.is_empty() {
+            ui.horizontal(|ui| {
+                let current = comparison.guard;
+                ui.button("Previous loop").clicked().then(|| {
+                    comparison.guard = crate::controls::step_loop(&loops, current, false);
+                });
+                ui.button("Next loop").clicked().then(|| {
+                    comparison.guard = crate::controls::step_loop(&loops, current, true);
+                });
+            });
+        }
+        ui.label(divergence_text(guards, stateinfo.camera_target));
+        ui.label(divergence_text(guards, comparison.guard));
+    });
+    if comparison.enabled {
+        // a line between the two halves
+        let rect = ctx.screen_rect();
+        ctx.layer_painter(egui::LayerId::background()).vline(
+            rect.center().x,
+            rect.y_range(),
+            egui::Stroke::new(2., egui::Color32::WHITE),
+        );
+    }
+}
+
+/// Adds or removes the right hand camera, and keeps it on the compared guard.
+pub fn compare_camera(
+    mut commands: Commands,
+    comparison: Res<Comparison>,
+    time: Res<Time>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    main_camera: Query<&OrthographicProjection, (With<Camera2d>, Without<CompareCamera>)>,
+    mut compare: Query<(Entity, &mut Camera, &mut Transform, &mut OrthographicProjection), With<CompareCamera>>,
+    guards: Query<(&Transform, &Guard), Without<Camera2d>>,
+) {
+    let Ok(window) = windows.get_single() else { return; };
+    if !comparison.enabled {
+        for (entity, ..) in compare.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    let scale = main_camera.get_single().map(|p| p.scale).unwrap_or(1.);
+    let (width, height) = (window.physical_width(), window.physical_height());
+    let viewport = Viewport {
+        physical_position: UVec2::new(width / 2, 0),
+        physical_size: UVec2::new((width - width / 2).max(1), height.max(1)),
+        ..default()
+    };
+    let target = guards.iter().find(|(_, g)| g.display_index == comparison.guard).map(|(t, _)| t.translation);
+    let Ok((_, mut camera, mut transform, mut projection)) = compare.get_single_mut() else {
+        let start = target.unwrap_or_default();
+        commands.spawn((
+            Camera2d,
+            Camera {
+                order: 1,
+                viewport: Some(viewport),
+                ..default()
+            },
+            OrthographicProjection{scale, ..OrthographicProjection::default_2d()},
+            Transform::from_translation(Vec3::new(start.x, start.y, 0.)),
+            CompareCamera,
+        ));
+        return;
+    };
+    camera.viewport = Some(viewport);
+    projection.scale = scale;
+    if let Some(target) = target {
+        let direction = Vec3::new(target.x, target.y, transform.translation.z);
+        transform.translation.smooth_nudge(&direction, CAMERA_DECAY_RATE, time.delta_secs());
+    }
+}
+
+/// While comparing, only the two compared guards, their trails and obstacles are shown,
+/// with a yellow square under the space where each one leaves the part 1 path.
+#[allow(clippy::type_complexity)]
+pub fn compare_overlay(
+    mut commands: Commands,
+    comparison: Res<Comparison>,
+    stateinfo: Res<StateInfo>,
+    rooms: Res<AllRooms>,
+    mut shown: Query<(&mut Visibility, Option<&Guard>, Option<&TrailEntity>, Option<&Obstacle>), Or<(With<Guard>, With<TrailEntity>, With<Obstacle>)>>,
+    markers: Query<(Entity, &DivergenceMarker)>,
+    mut last: Local<Option<(usize, usize)>>,
+) {
+    let pair = comparison.enabled.then_some((stateinfo.camera_target, comparison.guard));
+    for (mut vis, guard, trail, obstacle) in shown.iter_mut() {
+        let index = guard.map(|g| g.display_index)
+            .or(trail.map(|t| t.guard_index))
+            .or(obstacle.map(|o| o.0));
+        let visible = match (pair, index) {
+            (Some((left, right)), Some(index)) => index == left || index == right,
+            _ => true,
+        };
+        let wanted = if visible { Visibility::Inherited } else { Visibility::Hidden };
+        if *vis != wanted {
+            *vis = wanted;
+        }
+    }
+    if *last == pair { return; }
+    *last = pair;
+    for (entity, _) in markers.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    let (Some((left, right)), Some((_, guards))) = (pair, rooms.get_room(stateinfo.room_idx)) else { return; };
+    let Some(part1) = guards.iter().find(|g| g.display_index == 0) else { return; };
+    for index in [left, right] {
+        let Some(guard) = guards.iter().find(|g| g.display_index == index) else { continue; };
+        let Some(step) = divergence_step(part1, guard) else { continue; };
+        let (_, (x, y)) = guard.trail[step];
+        commands.spawn((
+            Sprite {
+                color: Color::srgb(1., 0.9, 0.2),
+                custom_size: Some(Vec2::new(SCALED_CELL_SIZE*0.9, SCALED_CELL_SIZE*0.9)),
+                ..default()
+            },
+            Transform::from_translation(Vec3::new(x as f32 * SCALED_CELL_SIZE, y as f32 * -SCALED_CELL_SIZE, 0.85)),
+            Visibility::default(),
+            DivergenceMarker(index),
+        ));
+    }
+}
+
+/// Leaving part 2 ends the comparison.
+pub fn end_compare(
+    mut commands: Commands,
+    mut comparison: ResMut<Comparison>,
+    cameras: Query<Entity, With<CompareCamera>>,
+    markers: Query<Entity, With<DivergenceMarker>>,
+) {
+    comparison.enabled = false;
+    for entity in cameras.iter().chain(markers.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+}
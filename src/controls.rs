@@ -1,9 +1,17 @@
 use crate::types::*;
+use crate::bundle::*;
+use crate::report::Report;
+use crate::trails;
+use crate::raster::{RenderOptions, TrailMode};
 use bevy::prelude::*;
+use bevy::ecs::world::CommandQueue;
+use bevy::tasks::AsyncComputeTaskPool;
 use bevy::ui::ZIndex;
 use std::io::{self};
 use bevy::asset::{AssetLoader, io::Reader, LoadContext};
 use bevy::reflect::TypePath;
+use bevy::asset::RenderAssetUsages;
+use bevy::image::{CompressedImageFormats, ImageSampler, ImageType};
 use bevy_egui::{egui, EguiContexts};
 use egui::{Order, Id, Pos2};
 
@@ -17,6 +25,7 @@ pub fn add_examples(
         let file = asset_server.load("embedded://day6vis/examples/input".to_string() + &i.to_string() + ".txt");
         commands.spawn(TextHandle(file));
     }
+    commands.spawn(TextHandle(asset_server.load("embedded://day6vis/examples/input5.png")));
     stateinfo.examples_loaded = true;
 }
 
@@ -34,7 +43,7 @@ pub fn load_examples(
 }
 
 #[derive(Asset, TypePath, Debug)]
-pub struct TextAsset(pub String);
+pub struct TextAsset(pub Vec<RoomSection>);
 
 #[derive(Component)]
 pub struct TextHandle(pub Handle<TextAsset>);
@@ -50,15 +59,17 @@ impl AssetLoader for TextAssetLoader {
         &self,
         reader: &mut dyn Reader,
         _settings: &(),
-        _load_context: &mut LoadContext<'_>,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
         // Convert bytes to String
         if let Ok(val) = String::from_utf8(bytes) {
-            Ok(TextAsset(val))
+            let mut sections = split_bundle(&val);
+            name_from_file(&mut sections, load_context.path());
+            Ok(TextAsset(sections))
         } else {
-            Err(io::Error::new(io::ErrorKind::Other, "UTF-8 error"))
+            Err(io::Error::other("UTF-8 error"))
         }
     }
     fn extensions(&self) -> &[&str] {
@@ -66,6 +77,76 @@ impl AssetLoader for TextAssetLoader {
     }
 }
 
+/// Reads pixel-art rooms. Black pixels are obstacles and the guard is a colored pixel:
+/// red is `^`, green is `>`, blue is `v` and yellow is `<`. Everything else is floor.
+#[derive(Default)]
+pub struct RoomImageLoader;
+
+impl AssetLoader for RoomImageLoader {
+    type Asset = TextAsset;
+    type Settings = ();
+    type Error = std::io::Error;
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let section = section_from_png(&bytes, load_context.path())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(TextAsset(vec![section]))
+    }
+    fn extensions(&self) -> &[&str] {
+        &["png"]
+    }
+}
+
+pub fn section_from_png(bytes: &[u8], path: &std::path::Path) -> Result<RoomSection, String> {
+    let image = Image::from_buffer(
+        bytes,
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::default(),
+    ).map_err(|e| e.to_string())?;
+    let pixels = image.try_into_dynamic().map_err(|e| e.to_string())?.to_rgba8();
+    let mut body = String::new();
+    for y in 0..pixels.height() {
+        for x in 0..pixels.width() {
+            body.push(pixel_glyph(pixels.get_pixel(x, y).0));
+        }
+        body.push('\n');
+    }
+    let mut section = RoomSection::new(body);
+    section.name = path.file_stem().map(|n|n.to_string_lossy().to_string());
+    section.source = Some(path.to_string_lossy().to_string());
+    Ok(section)
+}
+
+fn pixel_glyph([r, g, b, a]: [u8; 4]) -> char {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    if a < 128 {
+        '.'
+    } else if max < 64 {
+        '#'
+    } else if max - min < 128 {
+        '.'
+    } else if r > 128 && g > 128 {
+        '<'
+    } else if r >= g && r >= b {
+        '^'
+    } else if g >= b {
+        '>'
+    } else {
+        'v'
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn handle_input(
     mut contexts: EguiContexts,
     mut commands: Commands,
@@ -73,13 +154,23 @@ pub fn handle_input(
     rooms: Res<AllRooms>,
     mut pending_text: ResMut<PendingText>,
     mut current_error: ResMut<CurrentError>,
+    mut current_glyphs: ResMut<CurrentGlyphs>,
+    mut generator: ResMut<GeneratorSettings>,
     err_query: Query<(Entity, &ErrorBox)>,
+    generating: Query<(), With<GenerateRoom>>,
 ) {
     egui::Area::new(Id::new("input_area")).order(Order::Background).show(contexts.ctx_mut(), |ui| {
         ui.vertical(|ui| {
             ui.label("Select a saved room, then click on Part 1:");
-            for i in 0..rooms.len() {
-                ui.radio_value(&mut stateinfo.room_idx, Some(i), i.to_string());
+            for (room,_) in rooms.iter() {
+                let radio = ui.radio_value(&mut stateinfo.room_idx, Some(room.index), room.label());
+                let mut info = room.source.clone().unwrap_or_default();
+                if !room.notes.is_empty() {
+                    info = format!("{}\n{}", info, room.notes).trim().to_string();
+                }
+                if !info.is_empty() {
+                    radio.on_hover_text(info);
+                }
             }
         });
         ui.horizontal(|ui| {
@@ -90,14 +181,94 @@ pub fn handle_input(
                 for (room,_) in rooms.iter() {
                     if Some(room.index) == stateinfo.room_idx {
                         pending_text.0.clear();
-                        pending_text.0 = format!("{}", room);
+                        let mut section = RoomSection::from_room(room);
+                        if stateinfo.compact_edit {
+                            section.body = format!("{:#}", room);
+                        } else if let Some(glyphs) = GlyphMap::from_name(&current_glyphs.0) {
+                            section.body = room.to_string_with(&glyphs);
+                            if glyphs != GlyphMap::default() {
+                                section.glyphs = Some(current_glyphs.0.clone());
+                            }
+                        }
+                        pending_text.0 = section.to_string();
                     }
                 }
             });
             ui.button("Submit").clicked().then(|| {
-                commands.spawn(InputText(pending_text.0.clone()));
+                let mut sections = split_bundle(&pending_text.0);
+                for section in sections.iter_mut() {
+                    section.glyphs.get_or_insert_with(|| current_glyphs.0.clone());
+                }
+                commands.spawn(InputText(sections));
                 pending_text.0.clear();
             });
+            ui.checkbox(&mut stateinfo.compact_edit, "Compact");
+            egui::ComboBox::from_label("Glyphs")
+                .selected_text(current_glyphs.0.clone())
+                .show_ui(ui, |ui| {
+                    for name in GlyphMap::PRESETS {
+                        ui.selectable_value(&mut current_glyphs.0, name.to_string(), name);
+                    }
+                });
+        });
+        ui.collapsing("Generate", |ui| {
+            use crate::generator::*;
+            let options = &mut generator.0;
+            ui.horizontal(|ui| {
+                ui.label("Size");
+                ui.add(egui::DragValue::new(&mut options.width).range(1..=500));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut options.height).range(1..=500));
+                ui.label("Density");
+                ui.add(egui::DragValue::new(&mut options.density).range(0.0..=1.0).speed(0.01));
+                ui.label("Seed");
+                ui.add(egui::DragValue::new(&mut options.seed));
+            });
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut options.initial_path, InitialPath::Any, "Any path");
+                ui.radio_value(&mut options.initial_path, InitialPath::Exits, "Path exits");
+                ui.radio_value(&mut options.initial_path, InitialPath::Loops, "Path loops");
+            });
+            ui.horizontal(|ui| {
+                let mut want_part1 = options.part1_at_least.is_some();
+                ui.checkbox(&mut want_part1, "Part 1 at least");
+                let mut min = options.part1_at_least.unwrap_or(1);
+                ui.add_enabled(want_part1, egui::DragValue::new(&mut min));
+                options.part1_at_least = want_part1.then_some(min);
+                let mut want_part2 = options.part2_at_least.is_some();
+                ui.checkbox(&mut want_part2, "Part 2 at least");
+                let mut min = options.part2_at_least.unwrap_or(1);
+                ui.add_enabled(want_part2, egui::DragValue::new(&mut min));
+                options.part2_at_least = want_part2.then_some(min);
+                if !generating.is_empty() {
+                    ui.add_enabled(false, egui::Button::new("Generating..."));
+                    return;
+                }
+                ui.button("Generate").clicked().then(|| {
+                    // trying rooms can mean solving part 2 hundreds of times, keep it off the ui thread
+                    let options = *options;
+                    let task = AsyncComputeTaskPool::get().spawn(async move {
+                        let generated = generate(&options);
+                        let mut command_queue = CommandQueue::default();
+                        command_queue.push(move |world: &mut World| match generated {
+                            Ok((section, seed)) => {
+                                world.spawn(InputText(vec![section]));
+                                // next click gives a different room
+                                if let Some(mut settings) = world.get_resource_mut::<GeneratorSettings>() {
+                                    settings.0.seed = seed.wrapping_add(1);
+                                }
+                            }
+                            Err(err) => {
+                                if let Some(mut current_error) = world.get_resource_mut::<CurrentError>() {
+                                    current_error.0 = err;
+                                }
+                            }
+                        });
+                        command_queue
+                    });
+                    commands.spawn(GenerateRoom(task));
+                });
+            });
         });
         ui.text_edit_multiline(&mut pending_text.0);
         for (ent, err) in err_query.iter() {
@@ -221,15 +392,30 @@ pub fn prog_update_system(
     }
 }
 
+/// The looping guard before or after `current`, wrapping around at either end.
+pub fn step_loop(loops: &[usize], current: usize, forward: bool) -> usize {
+    let next = if forward {
+        loops.iter().find(|i| **i > current).or(loops.first())
+    } else {
+        loops.iter().rev().find(|i| **i < current).or(loops.last())
+    };
+    next.copied().unwrap_or(current)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn guard_controls(
     mut contexts: EguiContexts,
     mut stateinfo: ResMut<StateInfo>,
     mut timer: ResMut<MoveTimer>,
     state: Res<State<AppState>>,
     rooms: Res<AllRooms>,
+    mut export: ResMut<PendingExport>,
+    mut playback: ResMut<Playback>,
+    mut camera_control: ResMut<CameraControl>,
+    live_guards: Query<&Guard>,
     st_but: Query<&GlobalTransform, With<StateButton>>,
 ) {
-    let Some((_, guards)) = rooms.get_room(stateinfo.room_idx) else {
+    let Some((room, guards)) = rooms.get_room(stateinfo.room_idx) else {
         return;
     };
     let Ok(global_transform) = st_but.get_single() else { return; };
@@ -241,15 +427,176 @@ pub fn guard_controls(
                 .text("Tick Rate").step_by(1.0),
         );
         timer.0.set_duration(std::time::Duration::from_millis(newtime));
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut camera_control.follow, "Follow guard");
+            ui.button("Fit room").clicked().then(|| {
+                camera_control.fit = true;
+            });
+        });
+        if let Some(focused) = live_guards.iter().find(|g| g.display_index == stateinfo.camera_target) {
+            ui.horizontal(|ui| {
+                ui.button(if playback.paused { "Play" } else { "Pause" }).clicked().then(|| {
+                    playback.paused = !playback.paused;
+                });
+                ui.button("<").on_hover_text("Step back").clicked().then(|| {
+                    playback.paused = true;
+                    playback.seek = Some(focused.trail_idx.saturating_sub(1));
+                });
+                ui.button(">").on_hover_text("Step forward").clicked().then(|| {
+                    playback.paused = true;
+                    playback.seek = Some(focused.trail_idx + 1);
+                });
+                let mut step = focused.trail_idx.min(focused.trail.len());
+                let timeline = ui.add(egui::Slider::new(&mut step, 0..=focused.trail.len()).text("Step"));
+                if timeline.changed() {
+                    playback.paused = true;
+                    playback.seek = Some(step);
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.button("Trails (csv)").clicked().then(|| {
+                let rows = trails::trail_rows(guards);
+                export.0 = Some((format!("{}_trails.csv", room.label()), trails::to_csv(&rows)));
+            });
+            ui.button("Trails (json)").clicked().then(|| {
+                let rows = trails::trail_rows(guards);
+                export.0 = Some((format!("{}_trails.json", room.label()), trails::to_json(&rows)));
+            });
+            ui.button("Image (svg)").clicked().then(|| {
+                let mode = match *state.get() {
+                    AppState::Part2 if stateinfo.camera_target != 0 => TrailMode::Guard(stateinfo.camera_target),
+                    AppState::Part2 => TrailMode::AllLoops,
+                    _ => TrailMode::Part1,
+                };
+                let options = RenderOptions{mode, ..Default::default()};
+                export.0 = Some((format!("{}.svg", room.label()), crate::svg::room_svg(room, guards, &options)));
+            });
+            ui.button("Path (txt)").clicked().then(|| {
+                if let Some(text) = trails::path_text(room, guards, stateinfo.camera_target) {
+                    export.0 = Some((format!("{}_path_{}.txt", room.label(), stateinfo.camera_target), text));
+                }
+            });
+        });
         if *state.get() == AppState::Part2 {
             ui.add(
                 egui::Slider::new(&mut stateinfo.camera_target, 0..=(guards.len() - 1))
                     .text("Focused Guard").step_by(1.0),
             );
+            let loops = guards.loop_indices();
+            if !loops.is_empty() {
+                ui.horizontal(|ui| {
+                    let current = stateinfo.camera_target;
+                    ui.button("Previous loop").clicked().then(|| {
+                        stateinfo.camera_target = step_loop(&loops, current, false);
+                        camera_control.follow = true;
+                    });
+                    ui.button("Next loop").clicked().then(|| {
+                        stateinfo.camera_target = step_loop(&loops, current, true);
+                        camera_control.follow = true;
+                    });
+                    match loops.iter().position(|i| *i == current) {
+                        Some(pos) => ui.label(format!("Loop {} of {}", pos + 1, loops.len())),
+                        None => ui.label(format!("{} loops", loops.len())),
+                    };
+                });
+            }
+            if StateInfo::p2_loaded(room, guards) {
+                ui.horizontal(|ui| {
+                    ui.button("Report (md)").clicked().then(|| {
+                        let report = Report::new(room, guards);
+                        export.0 = Some((format!("{}_report.md", room.label()), report.to_markdown()));
+                    });
+                    ui.button("Report (json)").clicked().then(|| {
+                        let report = Report::new(room, guards);
+                        export.0 = Some((format!("{}_report.json", room.label()), report.to_json()));
+                    });
+                });
+            }
         };
     });
 }
 
+/// Clicking one of the X marks focuses the guard it was placed for.
+/// Presses that turn into a drag are left to the camera.
+#[allow(clippy::too_many_arguments)]
+pub fn select_obstacle(
+    mut contexts: EguiContexts,
+    mut stateinfo: ResMut<StateInfo>,
+    mut camera_control: ResMut<CameraControl>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    rooms: Res<AllRooms>,
+    windows: Query<&Window, With<bevy::window::PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    obstacles: Query<(&Obstacle, &Transform)>,
+    mut pressed_at: Local<Option<Vec2>>,
+) {
+    let Ok(window) = windows.get_single() else { return; };
+    if buttons.just_pressed(MouseButton::Left) {
+        *pressed_at = (!contexts.ctx_mut().is_pointer_over_area()).then(|| window.cursor_position()).flatten();
+    }
+    if !buttons.just_released(MouseButton::Left) { return; }
+    let (Some(start), Some(end)) = (pressed_at.take(), window.cursor_position()) else { return; };
+    if start.distance(end) > 4. { return; }
+    let Some((room, _)) = rooms.get_room(stateinfo.room_idx) else { return; };
+    let Some(cell) = crate::camera::cursor_cell(window, &camera, room) else { return; };
+    for (obstacle, transform) in obstacles.iter() {
+        let x = (transform.translation.x / SCALED_CELL_SIZE).round() as usize;
+        let y = (-transform.translation.y / SCALED_CELL_SIZE).round() as usize;
+        if (x, y) == cell {
+            stateinfo.camera_target = obstacle.0;
+            camera_control.follow = true;
+            return;
+        }
+    }
+}
+
+/// Room names can hold anything, keep the saved file in the current directory.
+fn safe_file_name(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() || "-_.".contains(c) { c } else { '_' }).collect()
+}
+
+/// Shows the last export so it can be copied, or saved to the current directory on native builds.
+pub fn export_window(
+    mut contexts: EguiContexts,
+    mut export: ResMut<PendingExport>,
+    // result of the last save, and the export it was for
+    mut saved: Local<Option<(String, String)>>,
+) {
+    let Some((file_name, contents)) = export.0.clone() else { return; };
+    let file_name = safe_file_name(&file_name);
+    if saved.as_ref().is_some_and(|(name, _)| *name != file_name) {
+        *saved = None;
+    }
+    let mut open = true;
+    egui::Window::new(file_name.clone()).id(Id::new("export_window")).open(&mut open).show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.button("Copy").clicked().then(|| {
+                ui.output_mut(|o| o.copied_text = contents.clone());
+            });
+            #[cfg(not(target_arch = "wasm32"))]
+            ui.button("Save").clicked().then(|| {
+                let message = match std::fs::write(&file_name, &contents) {
+                    Ok(_) => format!("Saved {}", file_name),
+                    Err(err) => format!("Could not save {}: {}", file_name, err),
+                };
+                *saved = Some((file_name.clone(), message));
+            });
+            if let Some((_, message)) = saved.as_ref() {
+                ui.label(message);
+            }
+        });
+        egui::ScrollArea::vertical().max_height(400.).show(ui, |ui| {
+            ui.monospace(&contents);
+        });
+    });
+    if !open {
+        export.0 = None;
+        *saved = None;
+    }
+}
+
+#[allow(clippy::type_complexity)]
 pub fn menu(
     mut next_state: ResMut<NextState<AppState>>,
     state: Res<State<AppState>>,
@@ -301,3 +648,26 @@ pub fn menu(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn room_names_make_plain_file_names() {
+        assert_eq!(safe_file_name("a/b_path_3.txt"), "a_b_path_3.txt");
+        assert_eq!(safe_file_name("../up.svg"), ".._up.svg");
+        assert_eq!(safe_file_name("room #2: big.md"), "room__2__big.md");
+    }
+
+    #[test]
+    fn step_loop_wraps_around() {
+        let loops = [2, 5, 9];
+        assert_eq!(step_loop(&loops, 5, true), 9);
+        assert_eq!(step_loop(&loops, 9, true), 2);
+        assert_eq!(step_loop(&loops, 2, false), 9);
+        // from a guard that doesn't loop, the nearest loop on that side
+        assert_eq!(step_loop(&loops, 4, false), 2);
+        assert_eq!(step_loop(&[], 4, true), 4);
+    }
+}
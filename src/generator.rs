@@ -0,0 +1,133 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::bundle::RoomSection;
+use crate::types::*;
+
+/// What the part 1 guard has to do in a generated room.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InitialPath {
+    Any,
+    Exits,
+    Loops,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneratorOptions {
+    pub width: usize,
+    pub height: usize,
+    /// Chance of each floor space being an obstacle, 0 to 1.
+    pub density: f32,
+    pub seed: u64,
+    pub initial_path: InitialPath,
+    /// Reject rooms where the part 1 guard visits fewer spaces than this.
+    pub part1_at_least: Option<usize>,
+    /// Reject rooms with fewer looping part 2 obstacles than this. Needs part 2 to be solved for every try.
+    pub part2_at_least: Option<usize>,
+    /// How many rooms to try before giving up on the constraints.
+    pub max_attempts: usize,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> GeneratorOptions {
+        GeneratorOptions {
+            width: 10,
+            height: 10,
+            density: 0.1,
+            seed: 0,
+            initial_path: InitialPath::Any,
+            part1_at_least: None,
+            part2_at_least: None,
+            max_attempts: 1000,
+        }
+    }
+}
+
+/// Tries seeds starting at `options.seed` until a room meets the constraints.
+/// The same options always give the same room. Returns the room and the seed that made it.
+pub fn generate(options: &GeneratorOptions) -> Result<(RoomSection, u64), String> {
+    if options.width == 0 || options.height == 0 {
+        return Err("Generated rooms need a width and height of at least 1".to_string());
+    }
+    if !(0.0..=1.0).contains(&options.density) {
+        return Err(format!("Density has to be between 0 and 1, not {}", options.density));
+    }
+    for attempt in 0..options.max_attempts.max(1) {
+        let seed = options.seed.wrapping_add(attempt as u64);
+        let mut section = random_section(options, seed);
+        let (room, mut guards) = section.part1()?;
+        let initial_is_loop = guards.first().map(|g| g.is_loop).unwrap_or(false);
+        let initial_ok = match options.initial_path {
+            InitialPath::Any => true,
+            InitialPath::Exits => !initial_is_loop,
+            InitialPath::Loops => initial_is_loop,
+        };
+        if !initial_ok || room.visited < options.part1_at_least.unwrap_or(0) {
+            continue;
+        }
+        section.expected_part1 = Some(room.visited);
+        if let Some(min) = options.part2_at_least {
+            crate::part1and2::part2_all(&room, &mut guards, crate::part1and2::available_threads());
+            let loops = guards.loop_count();
+            if loops < min {
+                continue;
+            }
+            section.expected_part2 = Some(loops);
+        }
+        return Ok((section, seed));
+    }
+    Err(format!("No room met the constraints after {} attempts", options.max_attempts.max(1)))
+}
+
+fn random_section(options: &GeneratorOptions, seed: u64) -> RoomSection {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let guard = (rng.gen_range(0..options.width), rng.gen_range(0..options.height));
+    let direction = match rng.gen_range(0..4) {
+        0 => Direction::Up,
+        1 => Direction::Right,
+        2 => Direction::Down,
+        _ => Direction::Left,
+    };
+    let density = options.density as f64;
+    let mut body = String::new();
+    for y in 0..options.height {
+        for x in 0..options.width {
+            let space = if (x, y) == guard {
+                RoomSpace::Guard(direction.clone())
+            } else if rng.gen_bool(density) {
+                RoomSpace::Obstacle
+            } else {
+                RoomSpace::Empty
+            };
+            body.push_str(&space.to_string());
+        }
+        body.push('\n');
+    }
+    let mut section = RoomSection::new(body);
+    section.name = Some(format!("Generated {}x{} #{}", options.width, options.height, seed));
+    section.source = Some("day6vis generate".to_string());
+    section.notes = format!("density={} seed={}", options.density, seed);
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_options_give_the_same_room() {
+        let options = GeneratorOptions{seed: 7, initial_path: InitialPath::Exits, ..Default::default()};
+        let (first, seed) = generate(&options).unwrap();
+        assert_eq!(generate(&options).unwrap(), (first.clone(), seed));
+        let (room, guards) = first.part1().unwrap();
+        assert!(!guards[0].is_loop);
+        assert_eq!(first.expected_part1, Some(room.visited));
+    }
+
+    #[test]
+    fn density_outside_zero_to_one_is_an_error() {
+        for density in [f32::NAN, -0.1, 1.5] {
+            assert!(generate(&GeneratorOptions{density, ..Default::default()}).is_err(), "{}", density);
+        }
+    }
+}
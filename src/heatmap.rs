@@ -0,0 +1,173 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy::color::ColorToPacked;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::types::*;
+
+/// What the heatmap counts for each floor space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeatSource {
+    /// Part 2 guards whose trail passes through the space.
+    CandidateTrails,
+    /// Times the part 1 guard walks into the space.
+    Part1Visits,
+    /// Looping part 2 guards whose cycle goes through the space.
+    Loops,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeatScale {
+    Linear,
+    Log,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeatmapSettings {
+    pub enabled: bool,
+    pub source: HeatSource,
+    pub scale: HeatScale,
+}
+
+#[derive(Resource)]
+pub struct Heatmap {
+    pub settings: HeatmapSettings,
+    /// Highest count in the current room, for the legend.
+    pub max: usize,
+}
+
+impl Default for Heatmap {
+    fn default() -> Heatmap {
+        Heatmap {
+            settings: HeatmapSettings{enabled: false, source: HeatSource::CandidateTrails, scale: HeatScale::Log},
+            max: 0,
+        }
+    }
+}
+
+pub fn heat_counts(guards: &AllGuards, source: HeatSource) -> HashMap<(usize, usize), usize> {
+    let mut counts = HashMap::new();
+    match source {
+        HeatSource::CandidateTrails => {
+            for guard in guards.iter().filter(|g| g.obstacle.is_some()) {
+                let cells: HashSet<(usize, usize)> = guard.trail.iter().map(|(_, pos)| *pos).collect();
+                for pos in cells {
+                    *counts.entry(pos).or_insert(0) += 1;
+                }
+            }
+        }
+        HeatSource::Part1Visits => {
+            let Some(guard) = guards.iter().find(|g| g.display_index == 0) else { return counts; };
+            let mut last = None;
+            for (_, pos) in guard.trail.iter() {
+                // turning in place pushes the same space again, that's not a new visit
+                if last != Some(*pos) {
+                    *counts.entry(*pos).or_insert(0) += 1;
+                }
+                last = Some(*pos);
+            }
+        }
+        HeatSource::Loops => {
+            for guard in guards.iter().filter(|g| g.obstacle.is_some()) {
+                let Some(start) = guard.cycle_start() else { continue; };
+                let cells: HashSet<(usize, usize)> = guard.trail[start..].iter().map(|(_, pos)| *pos).collect();
+                for pos in cells {
+                    *counts.entry(pos).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    counts
+}
+
+/// 0 to 1 for a count between 0 and `max`.
+pub fn intensity(count: usize, max: usize, scale: HeatScale) -> f32 {
+    if max == 0 { return 0.; }
+    match scale {
+        HeatScale::Linear => count as f32 / max as f32,
+        HeatScale::Log => (1. + count as f32).ln() / (1. + max as f32).ln(),
+    }
+}
+
+/// The count that gets a given intensity, for labelling the legend.
+pub fn count_at(intensity: f32, max: usize, scale: HeatScale) -> f32 {
+    match scale {
+        HeatScale::Linear => intensity * max as f32,
+        HeatScale::Log => (1. + max as f32).powf(intensity) - 1.,
+    }
+}
+
+/// Blue for little traffic through to red for the most.
+pub fn heat_color(intensity: f32) -> Color {
+    Color::hsv(240. * (1. - intensity.clamp(0., 1.)), 0.9, 0.9)
+}
+
+const FLOOR: Color = Color::srgb(0.5, 0.5, 0.5);
+
+/// Tints the floor tiles whenever the settings, the room or the number of solved guards change.
+pub fn apply_heatmap(
+    mut heatmap: ResMut<Heatmap>,
+    rooms: Res<AllRooms>,
+    stateinfo: Res<StateInfo>,
+    mut last: Local<Option<(HeatmapSettings, Option<usize>, usize)>>,
+    new_tiles: Query<(), Added<Space>>,
+    mut tiles: Query<(&Space, &mut Sprite)>,
+) {
+    let Some((room, guards)) = rooms.get_room(stateinfo.room_idx) else { return; };
+    let current = (heatmap.settings, stateinfo.room_idx, guards.len());
+    // entering part 1 or 2 spawns the tiles gray again
+    if *last == Some(current) && new_tiles.is_empty() { return; }
+    *last = Some(current);
+    let counts = if heatmap.settings.enabled { heat_counts(guards, heatmap.settings.source) } else { HashMap::new() };
+    heatmap.max = counts.values().copied().max().unwrap_or(0);
+    for (space, mut sprite) in tiles.iter_mut() {
+        // obstacle sprites share the Space component, only the floor gets tinted
+        if room[space.x][space.y] == RoomSpace::Obstacle { continue; }
+        sprite.color = match counts.get(&(space.x, space.y)) {
+            Some(count) => heat_color(intensity(*count, heatmap.max, heatmap.settings.scale)),
+            None => FLOOR,
+        };
+    }
+}
+
+pub fn heatmap_controls(
+    mut contexts: EguiContexts,
+    mut heatmap: ResMut<Heatmap>,
+) {
+    egui::Window::new("Heatmap").id(egui::Id::new("heatmap_window")).default_open(false).show(contexts.ctx_mut(), |ui| {
+        let settings = &mut heatmap.settings;
+        ui.checkbox(&mut settings.enabled, "Show heatmap");
+        ui.radio_value(&mut settings.source, HeatSource::CandidateTrails, "Candidate trails through each space");
+        ui.radio_value(&mut settings.source, HeatSource::Part1Visits, "Part 1 visits to each space");
+        ui.radio_value(&mut settings.source, HeatSource::Loops, "Loops going through each space");
+        ui.horizontal(|ui| {
+            ui.label("Scale");
+            ui.radio_value(&mut settings.scale, HeatScale::Linear, "Linear");
+            ui.radio_value(&mut settings.scale, HeatScale::Log, "Log");
+        });
+        if !settings.enabled { return; }
+        let (max, scale) = (heatmap.max, heatmap.settings.scale);
+        // legend: the color ramp with the counts at each end and in the middle
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(200., 16.), egui::Sense::hover());
+        let steps = 50;
+        for i in 0..steps {
+            let t = i as f32 / (steps - 1) as f32;
+            let [r, g, b, _] = heat_color(t).to_srgba().to_u8_array();
+            let x0 = rect.left() + rect.width() * i as f32 / steps as f32;
+            let x1 = rect.left() + rect.width() * (i + 1) as f32 / steps as f32;
+            ui.painter().rect_filled(
+                egui::Rect::from_min_max(egui::pos2(x0, rect.top()), egui::pos2(x1, rect.bottom())),
+                0.,
+                egui::Color32::from_rgb(r, g, b),
+            );
+        }
+        ui.horizontal(|ui| {
+            ui.label("0");
+            ui.add_space(70.);
+            ui.label(format!("{:.0}", count_at(0.5, max, scale)));
+            ui.add_space(70.);
+            ui.label(max.to_string());
+        });
+    });
+}
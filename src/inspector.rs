@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::types::*;
+
+/// Candidates listed by index before the rest are only counted.
+const MAX_LISTED: usize = 20;
+
+/// Lines describing one space: what's on it, how the part 1 guard walked it,
+/// and which part 2 candidates have to do with it.
+pub fn describe_cell(room: &Room, guards: &AllGuards, pos: (usize, usize)) -> Vec<String> {
+    let (x, y) = pos;
+    let mut lines = vec![format!("Space {},{}: {:?}", x, y, room[x][y])];
+    if let Some(part1) = guards.iter().find(|g| g.display_index == 0) {
+        let mut visits = 0;
+        let mut directions: Vec<Direction> = Vec::new();
+        let mut last = None;
+        for (dir, step) in part1.trail.iter() {
+            if *step == pos {
+                // turning in place adds the same space again, with the new direction
+                if last != Some(*step) { visits += 1; }
+                if !directions.contains(dir) { directions.push(dir.clone()); }
+            }
+            last = Some(*step);
+        }
+        if visits > 0 {
+            let directions: Vec<String> = directions.iter().map(|d| format!("{:?}", d)).collect();
+            lines.push(format!("Part 1 guard passes {} time{}, facing {}", visits, if visits == 1 { "" } else { "s" }, directions.join(", ")));
+        } else {
+            lines.push("Part 1 guard never comes here".to_string());
+        }
+    }
+    let crossing: Vec<&Guard> = guards.iter()
+        .filter(|g| g.obstacle.is_some() && g.trail.iter().any(|(_, step)| *step == pos))
+        .collect();
+    if !crossing.is_empty() {
+        let mut listed: Vec<String> = crossing.iter().take(MAX_LISTED)
+            .map(|g| if g.is_loop { format!("{} (loops)", g.display_index) } else { g.display_index.to_string() })
+            .collect();
+        if crossing.len() > MAX_LISTED {
+            listed.push(format!("and {} more", crossing.len() - MAX_LISTED));
+        }
+        lines.push(format!("{} candidate trails cross here: {}", crossing.len(), listed.join(", ")));
+    }
+    if let Some(candidate) = guards.iter().find(|g| g.obstacle == Some(pos)) {
+        lines.push(format!("Obstacle here for guard {} {}", candidate.display_index,
+            if candidate.is_loop { "makes a loop" } else { "lets the guard leave" }));
+    } else if room.to_check.contains(&pos) {
+        lines.push("Candidate obstacle, still being checked".to_string());
+    }
+    lines
+}
+
+/// Room index, hovered space and number of guards solved so far.
+type InspectedCell = (usize, (usize, usize), usize);
+
+/// Tooltip for the space under the mouse, with the loops entered there.
+pub fn cell_inspector(
+    mut contexts: EguiContexts,
+    stateinfo: Res<StateInfo>,
+    rooms: Res<AllRooms>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    entries: Query<&CycleEntry>,
+    mut cached: Local<Option<(InspectedCell, Vec<String>)>>,
+) {
+    let Some((room, guards)) = rooms.get_room(stateinfo.room_idx) else { return; };
+    let Ok(window) = windows.get_single() else { return; };
+    let Some(cell) = crate::camera::cursor_cell(window, &camera, room) else { return; };
+    let ctx = contexts.ctx_mut();
+    if ctx.is_pointer_over_area() { return; }
+    // describing a space walks every candidate's trail, only do it when something changed
+    let key = (room.index, cell, guards.len());
+    if cached.as_ref().map(|(k, _)| *k) != Some(key) {
+        *cached = Some((key, describe_cell(room, guards, cell)));
+    }
+    let mut lines = cached.as_ref().map(|(_, lines)| lines.clone()).unwrap_or_default();
+    let mut here: Vec<&CycleEntry> = entries.iter().filter(|e| e.pos == cell).collect();
+    here.sort_by_key(|e| e.guard_index);
+    for entry in here {
+        lines.push(format!("Guard {} enters its loop here at step {}, cycle length {}", entry.guard_index, entry.step, entry.cycle_len));
+    }
+    egui::show_tooltip_at_pointer(ctx, egui::LayerId::background(), egui::Id::new("cell_inspector"), |ui| {
+        for line in lines {
+            ui.label(line);
+        }
+    });
+}
@@ -1,9 +1,26 @@
 use wasm_bindgen::prelude::*;
-mod app;
-mod part1and2;
-mod types;
-mod controls;
-mod camera;
+pub mod app;
+pub mod part1and2;
+pub mod types;
+pub mod controls;
+pub mod camera;
+pub mod heatmap;
+pub mod minimap;
+pub mod inspector;
+pub mod compare;
+pub mod bundle;
+pub mod report;
+pub mod trails;
+pub mod raster;
+pub mod animation;
+pub mod svg;
+pub mod generator;
+pub mod bench;
+pub mod verify;
+pub mod batch;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tui;
+pub mod cli;
 
 #[wasm_bindgen]
 pub fn init() {
@@ -1,9 +1,7 @@
-mod app;
-mod part1and2;
-mod types;
-mod controls;
-mod camera;
-
 fn main() {
-    crate::app::run();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = day6vis::cli::run(&args) {
+        std::process::exit(code);
+    }
+    day6vis::app::run();
 }
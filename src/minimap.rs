@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use bevy::color::ColorToPacked;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::types::*;
+
+/// Longest side of the minimap, in points.
+const MINIMAP_SIZE: f32 = 200.;
+
+fn to_color32(color: Color) -> egui::Color32 {
+    let [r, g, b, a] = color.to_srgba().to_u8_array();
+    egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+/// The room's floor and obstacles, one pixel per space.
+fn room_image(room: &Room, width: usize, height: usize) -> egui::ColorImage {
+    let mut image = egui::ColorImage::new([width, height], to_color32(Color::srgb(0.5, 0.5, 0.5)));
+    for (x, col) in room.iter().enumerate() {
+        for (y, space) in col.iter().enumerate() {
+            if *space == RoomSpace::Obstacle {
+                image.pixels[y * width + x] = to_color32(Color::srgb(0.15, 0.15, 0.15));
+            }
+        }
+    }
+    image
+}
+
+/// The whole room in a corner, with every guard, the loop obstacles and what the camera sees.
+/// Clicking or dragging on it moves the camera there.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn minimap(
+    mut contexts: EguiContexts,
+    rooms: Res<AllRooms>,
+    stateinfo: Res<StateInfo>,
+    state: Res<State<AppState>>,
+    mut control: ResMut<CameraControl>,
+    guards: Query<&Guard>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut camera: Query<(&mut Transform, &OrthographicProjection), (With<Camera2d>, Without<CompareCamera>)>,
+    mut texture: Local<Option<(usize, egui::TextureHandle)>>,
+) {
+    let Some((room, all_guards)) = rooms.get_room(stateinfo.room_idx) else { return; };
+    let (Ok(window), Ok((mut cam_transform, projection))) = (windows.get_single(), camera.get_single_mut()) else { return; };
+    let (width, height) = (room.len(), room.height());
+    if width == 0 || height == 0 { return; }
+    let cell = MINIMAP_SIZE / width.max(height) as f32;
+    let ctx = contexts.ctx_mut();
+    // rooms don't change once loaded, so the obstacles are only drawn once per room
+    if texture.as_ref().map(|(index, _)| *index) != Some(room.index) {
+        let handle = ctx.load_texture("minimap", room_image(room, width, height), egui::TextureOptions::NEAREST);
+        *texture = Some((room.index, handle));
+    }
+    let Some((_, texture)) = texture.as_ref() else { return; };
+    egui::Area::new(egui::Id::new("minimap"))
+        .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10., -10.))
+        .show(ctx, |ui| {
+            let size = egui::vec2(width as f32 * cell, height as f32 * cell);
+            let (response, painter) = ui.allocate_painter(size, egui::Sense::click_and_drag());
+            let origin = response.rect.min;
+            let cell_rect = |x: usize, y: usize, shrink: f32| {
+                egui::Rect::from_min_size(
+                    origin + egui::vec2(x as f32 * cell, y as f32 * cell),
+                    egui::vec2(cell, cell),
+                ).shrink(cell * shrink)
+            };
+            painter.image(
+                texture.id(),
+                response.rect,
+                egui::Rect::from_min_max(egui::pos2(0., 0.), egui::pos2(1., 1.)),
+                egui::Color32::WHITE,
+            );
+            if *state.get() == AppState::Part2 {
+                for guard in all_guards.iter().filter(|g| g.is_loop) {
+                    let Some((x, y)) = guard.obstacle else { continue; };
+                    painter.rect_filled(cell_rect(x, y, 0.), 0., to_color32(color_from_idx(guard.display_index)));
+                }
+            }
+            for guard in guards.iter() {
+                let Some((_, (x, y))) = guard.get_loc() else { continue; };
+                let radius = if guard.display_index == stateinfo.camera_target { cell.max(3.) } else { (cell / 2.).max(1.5) };
+                painter.circle_filled(cell_rect(x, y, 0.).center(), radius, to_color32(color_from_idx(guard.display_index)));
+            }
+            // world positions are cell centers, so a cell spans half a cell either side
+            let to_map = |world: Vec2| origin + egui::vec2(
+                (world.x / SCALED_CELL_SIZE + 0.5) * cell,
+                (-world.y / SCALED_CELL_SIZE + 0.5) * cell,
+            );
+            let half_view = Vec2::new(window.width(), window.height()) * projection.scale / 2.;
+            let center = cam_transform.translation.truncate();
+            let view = egui::Rect::from_two_pos(to_map(center - half_view), to_map(center + half_view));
+            painter.rect_stroke(view.intersect(response.rect), 0., egui::Stroke::new(1.5, egui::Color32::WHITE));
+            if response.clicked() || response.dragged() {
+                if let Some(pos) = response.interact_pointer_pos() {
+                    let offset = pos - origin;
+                    control.follow = false;
+                    cam_transform.translation.x = (offset.x / cell - 0.5) * SCALED_CELL_SIZE;
+                    cam_transform.translation.y = -(offset.y / cell - 0.5) * SCALED_CELL_SIZE;
+                }
+            }
+        });
+}
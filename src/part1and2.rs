@@ -1,7 +1,7 @@
 use crate::types::*;
 
-pub fn part1(input: String) -> Result<(Room,AllGuards), RoomError> {
-    let mut board = Room::from_string(input)?;
+pub fn part1(input: String, glyphs: &GlyphMap) -> Result<(Room,AllGuards), RoomError> {
+    let mut board = Room::from_string_with(input, glyphs)?;
     let boardx = board.len();
     let boardy = board[0].len();
     let mut trail = Trail::new();
@@ -24,6 +24,31 @@ pub fn part2(room: &Room, initial_path_is_loop: bool, obsx: usize, obsy: usize,
     Guard::new(trail, Some((obsx, obsy)), is_loop, index) 
 }
 
+/// Cores to spread work over when nothing says otherwise.
+pub fn available_threads() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Runs part 2 for every obstacle in `room.to_check` without the bevy task pool,
+/// spread over `threads` threads. Guards come back sorted by display index.
+pub fn part2_all(room: &Room, guards: &mut AllGuards, threads: usize) {
+    let init_is_loop = guards.first().map(|g| g.is_loop).unwrap_or(false);
+    let threads = threads.max(1);
+    let candidates: Vec<(usize,(usize,usize))> = room.to_check.iter().copied().enumerate().collect();
+    let chunk_size = candidates.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = candidates.chunks(chunk_size).map(|chunk| {
+            scope.spawn(move || {
+                chunk.iter().map(|(i,(x,y))| part2(room, init_is_loop, *x, *y, i+1)).collect::<Vec<Guard>>()
+            })
+        }).collect();
+        for handle in handles {
+            guards.extend(handle.join().expect("part 2 worker panicked"));
+        }
+    });
+    guards.sort_by_idx();
+}
+
 fn check_for_loop(room: &mut Room, trail: &mut Trail, initial_path_is_loop: bool, obsx: usize, obsy: usize) -> bool {
     if obsx < room.len() && obsy < room[0].len() {
         if room[obsx][obsy] == RoomSpace::Obstacle {
@@ -111,3 +136,57 @@ fn deduplicate_vec<T: PartialEq>(vec: Vec<T>) -> Vec<T> {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("examples/input1.txt");
+
+    #[test]
+    fn example_answers() {
+        let (room, mut guards) = part1(EXAMPLE.to_string(), &GlyphMap::default()).unwrap();
+        assert_eq!(room.visited, 41);
+        part2_all(&room, &mut guards, 2);
+        let loops: Vec<(usize,usize)> = guards.iter().filter(|g| g.is_loop).filter_map(|g| g.obstacle).collect();
+        assert_eq!(loops.len(), 6);
+        assert!(loops.contains(&(3,6)) && loops.contains(&(6,7)));
+    }
+
+    // the first two loops drawn in the puzzle statement
+    #[test]
+    fn path_notation_matches_the_puzzle() {
+        let (room, _) = part1(EXAMPLE.to_string(), &GlyphMap::default()).unwrap();
+        let first = part2(&room, false, 3, 6, 1);
+        assert_eq!(room.to_path_string(&first.trail, &[(3,6)]), "\
+....#.....
+....+---+#
+....|...|.
+..#.|...|.
+....|..#|.
+....|...|.
+.#.O^---+.
+........#.
+#.........
+......#...
+");
+        let second = part2(&room, false, 6, 7, 2);
+        assert_eq!(room.to_path_string(&second.trail, &[(6,7)]), "\
+....#.....
+....+---+#
+....|...|.
+..#.|...|.
+..+-+-+#|.
+..|.|.|.|.
+.#+-^-+-+.
+......O.#.
+#.........
+......#...
+");
+    }
+
+    #[test]
+    fn room_without_guard_is_an_error() {
+        assert!(matches!(part1("...\n.#.".to_string(), &GlyphMap::default()), Err(RoomError::NoGuards)));
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use bevy::color::{Color, ColorToPacked};
+use image::{imageops, Rgba, RgbaImage};
+
+use crate::types::*;
+
+/// The embedded sprites, for drawing without an asset server.
+const SPRITES: [(&str, &[u8]); 25] = [
+    ("Up1.png", include_bytes!("sprites/Up1.png")),
+    ("Up2.png", include_bytes!("sprites/Up2.png")),
+    ("Up3.png", include_bytes!("sprites/Up3.png")),
+    ("Right1.png", include_bytes!("sprites/Right1.png")),
+    ("Right2.png", include_bytes!("sprites/Right2.png")),
+    ("Right3.png", include_bytes!("sprites/Right3.png")),
+    ("Down1.png", include_bytes!("sprites/Down1.png")),
+    ("Down2.png", include_bytes!("sprites/Down2.png")),
+    ("Down3.png", include_bytes!("sprites/Down3.png")),
+    ("Left1.png", include_bytes!("sprites/Left1.png")),
+    ("Left2.png", include_bytes!("sprites/Left2.png")),
+    ("Left3.png", include_bytes!("sprites/Left3.png")),
+    ("Obstacle0.png", include_bytes!("sprites/Obstacle0.png")),
+    ("Obstacle1.png", include_bytes!("sprites/Obstacle1.png")),
+    ("Obstacle2.png", include_bytes!("sprites/Obstacle2.png")),
+    ("Obstacle3.png", include_bytes!("sprites/Obstacle3.png")),
+    ("Obstacle4.png", include_bytes!("sprites/Obstacle4.png")),
+    ("Obstacle5.png", include_bytes!("sprites/Obstacle5.png")),
+    ("Obstacle6.png", include_bytes!("sprites/Obstacle6.png")),
+    ("Obstacle7.png", include_bytes!("sprites/Obstacle7.png")),
+    ("Obstacle8.png", include_bytes!("sprites/Obstacle8.png")),
+    ("Obstacle9.png", include_bytes!("sprites/Obstacle9.png")),
+    ("Obstacle10.png", include_bytes!("sprites/Obstacle10.png")),
+    ("Obstacle11.png", include_bytes!("sprites/Obstacle11.png")),
+    ("Obstacle12.png", include_bytes!("sprites/Obstacle12.png")),
+];
+
+/// Which trails end up in the picture.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrailMode {
+    Part1,
+    AllLoops,
+    Guard(usize),
+}
+
+impl TrailMode {
+    /// The guards drawn in this mode, in drawing order.
+    pub fn select<'a>(&self, guards: &'a AllGuards) -> Vec<&'a Guard> {
+        guards.iter().filter(|g| match self {
+            TrailMode::Part1 => g.display_index == 0,
+            TrailMode::AllLoops => g.makes_loop(),
+            TrailMode::Guard(idx) => g.display_index == *idx,
+        }).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    pub cell_size: u32,
+    pub mode: TrailMode,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions{cell_size: CELL_SIZE as u32, mode: TrailMode::Part1}
+    }
+}
+
+/// Draws rooms the way the bevy view does, on the cpu.
+pub struct Rasterizer {
+    pub options: RenderOptions,
+    sprites: HashMap<String, RgbaImage>,
+}
+
+impl Rasterizer {
+    pub fn new(options: RenderOptions) -> Rasterizer {
+        Rasterizer{options, sprites: HashMap::new()}
+    }
+
+    /// Room with every drawn guard's full trail and the guards at their start.
+    pub fn render(&mut self, room: &Room, guards: &AllGuards) -> RgbaImage {
+        let drawn = self.options.mode.select(guards);
+        let mut canvas = self.render_room(room);
+        for guard in &drawn {
+            self.draw_trail(&mut canvas, guard, guard.trail.len());
+        }
+        for guard in &drawn {
+            self.draw_loop_obstacle(&mut canvas, guard);
+        }
+        for guard in &drawn {
+            let mut start = (*guard).clone();
+            start.reset();
+            self.draw_guard(&mut canvas, &start);
+        }
+        canvas
+    }
+
+    /// Floor and obstacles only.
+    pub fn render_room(&mut self, room: &Room) -> RgbaImage {
+        let cell = self.options.cell_size;
+        let width = room.len() as u32;
+        let height = room.height() as u32;
+        let mut canvas = RgbaImage::from_pixel(width * cell, height * cell, to_rgba(Color::srgb(0.5, 0.5, 0.5)));
+        for (x, col) in room.iter().enumerate() {
+            for (y, space) in col.iter().enumerate() {
+                if *space == RoomSpace::Obstacle {
+                    // the view picks a random sprite, here it has to stay the same between runs
+                    let name = format!("Obstacle{}.png", (x * 31 + y * 17) % 13);
+                    if let Some(sprite) = self.sprite(&name, cell, cell) {
+                        imageops::overlay(&mut canvas, sprite, (x as u32 * cell) as i64, (y as u32 * cell) as i64);
+                    }
+                }
+            }
+        }
+        canvas
+    }
+
+    /// Trail squares for the first `steps` steps of the guard's trail.
+    pub fn draw_trail(&self, canvas: &mut RgbaImage, guard: &Guard, steps: usize) {
+        for step in 0..steps.min(guard.trail.len()) {
+            self.draw_trail_step(canvas, guard, step);
+        }
+    }
+
+    pub fn draw_trail_step(&self, canvas: &mut RgbaImage, guard: &Guard, step: usize) {
+        let Some((_, (x, y))) = guard.trail.get(step) else { return; };
+        let focused = match self.options.mode {
+            TrailMode::Guard(idx) => idx == guard.display_index,
+            _ => false,
+        };
+        let size = if focused { self.options.cell_size / 2 } else { self.options.cell_size / 4 };
+        let color = to_rgba(color_from_idx(guard.display_index));
+        self.fill_centered(canvas, *x, *y, size.max(1), size.max(1), color);
+    }
+
+    /// The X that marks where a part 2 guard's extra obstacle went.
+    pub fn draw_loop_obstacle(&self, canvas: &mut RgbaImage, guard: &Guard) {
+        let Some((x, y)) = guard.obstacle else { return; };
+        let cell = self.options.cell_size as i64;
+        let color = to_rgba(color_from_idx(guard.display_index));
+        let thickness = (cell / 6).max(1);
+        let (left, top) = (x as i64 * cell, y as i64 * cell);
+        // two bars one cell long, rotated 45 degrees, like guard_spawn
+        for i in (cell * 3 / 20)..(cell * 17 / 20) {
+            for t in 0..thickness {
+                let offset = t - thickness / 2;
+                put_pixel(canvas, left + i, top + i + offset, color);
+                put_pixel(canvas, left + i, top + cell - 1 - i + offset, color);
+            }
+        }
+    }
+
+    /// The guard sprite at the guard's current trail position.
+    pub fn draw_guard(&mut self, canvas: &mut RgbaImage, guard: &Guard) {
+        let Some((_, (x, y))) = guard.get_loc() else { return; };
+        let path = guard.get_sprite();
+        let name = path.rsplit('/').next().unwrap_or_default().to_string();
+        let scale = self.options.cell_size as f32 / CELL_SIZE;
+        let Some((w, h)) = self.natural_size(&name) else { return; };
+        let (w, h) = (((w as f32 * scale) as u32).max(1), ((h as f32 * scale) as u32).max(1));
+        let cell = self.options.cell_size as i64;
+        let left = x as i64 * cell + (cell - w as i64) / 2;
+        let top = y as i64 * cell + (cell - h as i64) / 2;
+        if let Some(sprite) = self.sprite(&name, w, h) {
+            imageops::overlay(canvas, sprite, left, top);
+        }
+    }
+
+    fn fill_centered(&self, canvas: &mut RgbaImage, x: usize, y: usize, w: u32, h: u32, color: Rgba<u8>) {
+        let cell = self.options.cell_size as i64;
+        let left = x as i64 * cell + (cell - w as i64) / 2;
+        let top = y as i64 * cell + (cell - h as i64) / 2;
+        for px in left..left + w as i64 {
+            for py in top..top + h as i64 {
+                put_pixel(canvas, px, py, color);
+            }
+        }
+    }
+
+    fn natural_size(&mut self, name: &str) -> Option<(u32, u32)> {
+        self.original(name).map(|img| (img.width(), img.height()))
+    }
+
+    fn original(&mut self, name: &str) -> Option<&RgbaImage> {
+        if !self.sprites.contains_key(name) {
+            let (_, bytes) = SPRITES.iter().find(|(n, _)| *n == name)?;
+            let decoded = image::load_from_memory_with_format(bytes, image::ImageFormat::Png).ok()?.to_rgba8();
+            self.sprites.insert(name.to_string(), decoded);
+        }
+        self.sprites.get(name)
+    }
+
+    fn sprite(&mut self, name: &str, w: u32, h: u32) -> Option<&RgbaImage> {
+        let key = format!("{}@{}x{}", name, w, h);
+        if !self.sprites.contains_key(&key) {
+            let resized = imageops::resize(self.original(name)?, w, h, imageops::FilterType::Triangle);
+            self.sprites.insert(key.clone(), resized);
+        }
+        self.sprites.get(&key)
+    }
+}
+
+fn put_pixel(canvas: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>) {
+    if x >= 0 && y >= 0 && (x as u32) < canvas.width() && (y as u32) < canvas.height() {
+        canvas.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+pub fn to_rgba(color: Color) -> Rgba<u8> {
+    Rgba(color.to_srgba().to_u8_array())
+}
@@ -0,0 +1,91 @@
+use serde::Serialize;
+use crate::types::*;
+
+#[derive(Debug, Serialize)]
+pub struct LoopEntry {
+    pub guard_index: usize,
+    pub obstacle: (usize, usize),
+    pub cycle_length: usize,
+    pub trail_length: usize,
+}
+
+/// Summary of a solved room, meant to be shared once part 2 is done.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub name: String,
+    pub notes: String,
+    pub width: usize,
+    pub height: usize,
+    pub guard_start: Option<(usize, usize)>,
+    pub guard_direction: Option<String>,
+    pub part1_visited: usize,
+    pub part1_is_loop: bool,
+    pub part2_loops: usize,
+    pub expected_part1: Option<usize>,
+    pub expected_part2: Option<usize>,
+    pub loops: Vec<LoopEntry>,
+}
+
+impl Report {
+    pub fn new(room: &Room, guards: &AllGuards) -> Report {
+        let start = room.initial_guard();
+        let loops: Vec<LoopEntry> = guards.iter()
+            .filter(|g| g.is_loop)
+            .filter_map(|g| Some(LoopEntry {
+                guard_index: g.display_index,
+                obstacle: g.obstacle?,
+                cycle_length: g.cycle_len().unwrap_or(0),
+                trail_length: g.trail.len(),
+            }))
+            .collect();
+        Report {
+            name: room.label(),
+            notes: room.notes.clone(),
+            width: room.len(),
+            height: room.height(),
+            guard_start: start.as_ref().map(|(_,pos)| *pos),
+            guard_direction: start.map(|(dir,_)| RoomSpace::Guard(dir).to_string()),
+            part1_visited: room.visited,
+            part1_is_loop: guards.first().map(|g| g.is_loop).unwrap_or(false),
+            part2_loops: loops.len(),
+            expected_part1: room.expected_part1,
+            expected_part2: room.expected_part2,
+            loops,
+        }
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# Room {}\n\n", self.name);
+        if !self.notes.is_empty() {
+            out.push_str(&format!("{}\n\n", self.notes));
+        }
+        out.push_str(&format!("- Size: {}x{}\n", self.width, self.height));
+        if let (Some((x,y)), Some(dir)) = (self.guard_start, &self.guard_direction) {
+            out.push_str(&format!("- Guard start: {},{} facing `{}`\n", x, y, dir));
+        }
+        out.push_str(&format!("- Part 1 visited: {}{}\n", self.part1_visited, expected_note(self.part1_visited, self.expected_part1)));
+        if self.part1_is_loop {
+            out.push_str("- Part 1 path loops\n");
+        }
+        out.push_str(&format!("- Part 2 loops: {}{}\n", self.part2_loops, expected_note(self.part2_loops, self.expected_part2)));
+        if !self.loops.is_empty() {
+            out.push_str("\n| Guard | Obstacle | Cycle length | Trail length |\n");
+            out.push_str("|---|---|---|---|\n");
+            for entry in &self.loops {
+                out.push_str(&format!("| {} | {},{} | {} | {} |\n",
+                    entry.guard_index, entry.obstacle.0, entry.obstacle.1, entry.cycle_length, entry.trail_length));
+            }
+        }
+        out
+    }
+}
+
+fn expected_note(actual: usize, expected: Option<usize>) -> String {
+    match expected {
+        Some(e) if e == actual => " (as expected)".to_string(),
+        Some(e) => format!(" (expected {})", e),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt::Write;
+
+use bevy::color::Color;
+
+use crate::raster::{RenderOptions, TrailMode};
+use crate::types::*;
+
+/// Vector version of `Rasterizer::render`. Output only depends on its input so it can be diffed.
+pub fn room_svg(room: &Room, guards: &AllGuards, options: &RenderOptions) -> String {
+    let cell = options.cell_size as f32;
+    let width = room.len() as f32 * cell;
+    let height = room.height() as f32 * cell;
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#, width, height, width, height);
+    let _ = writeln!(out, r#"<rect width="{}" height="{}" fill="{}"/>"#, width, height, hex(Color::srgb(0.5, 0.5, 0.5)));
+    let _ = writeln!(out, r#"<g id="obstacles" fill="{}">"#, hex(Color::srgb(0.15, 0.15, 0.15)));
+    for y in 0..room.height() {
+        for (x, col) in room.iter().enumerate() {
+            if col[y] == RoomSpace::Obstacle {
+                let _ = writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{}"/>"#, x as f32 * cell, y as f32 * cell, cell, cell);
+            }
+        }
+    }
+    let _ = writeln!(out, "</g>");
+    let drawn = options.mode.select(guards);
+    let _ = writeln!(out, r#"<g id="trails" fill="none" stroke-linejoin="round" stroke-linecap="round">"#);
+    for guard in &drawn {
+        let points: Vec<String> = guard.trail.iter()
+            .map(|(_, (x, y))| format!("{},{}", (*x as f32 + 0.5) * cell, (*y as f32 + 0.5) * cell))
+            .collect();
+        let focused = options.mode == TrailMode::Guard(guard.display_index);
+        let stroke = if focused { cell / 2. } else { cell / 4. };
+        let _ = writeln!(out, r#"<polyline id="guard-{}" stroke="{}" stroke-width="{}" points="{}"/>"#,
+            guard.display_index, hex(color_from_idx(guard.display_index)), stroke, points.join(" "));
+    }
+    let _ = writeln!(out, "</g>");
+    let _ = writeln!(out, r#"<g id="loop-obstacles">"#);
+    for guard in &drawn {
+        let Some((x, y)) = guard.obstacle else { continue; };
+        let (cx, cy) = ((x as f32 + 0.5) * cell, (y as f32 + 0.5) * cell);
+        // the same two rotated bars guard_spawn uses
+        for angle in [-45, 45] {
+            let _ = writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" transform="rotate({} {} {})"/>"#,
+                cx - cell / 2., cy - cell / 12., cell, cell / 6., hex(color_from_idx(guard.display_index)), angle, cx, cy);
+        }
+    }
+    let _ = writeln!(out, "</g>");
+    if let Some((dir, (x, y))) = room.initial_guard() {
+        // an arrow pointing the way the guard starts out
+        let (cx, cy) = ((x as f32 + 0.5) * cell, (y as f32 + 0.5) * cell);
+        let points: Vec<String> = [(0., -0.35), (-0.3, 0.3), (0.3, 0.3)].iter().map(|(px, py)| {
+            let (px, py) = match dir {
+                Direction::Up => (*px, *py),
+                Direction::Right => (-py, *px),
+                Direction::Down => (-px, -py),
+                Direction::Left => (*py, -px),
+            };
+            format!("{},{}", cx + px * cell, cy + py * cell)
+        }).collect();
+        let _ = writeln!(out, r#"<polygon id="guard-start" fill="white" points="{}"/>"#, points.join(" "));
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn hex(color: Color) -> String {
+    color.to_srgba().to_hex()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_golden_output() {
+        let section = &crate::bundle::split_bundle(include_str!("examples/input1.txt"))[0];
+        let (room, guards) = crate::cli::solve_section(section).unwrap();
+        let options = RenderOptions{cell_size: 10, mode: TrailMode::Guard(18)};
+        // regenerate with `day6vis svg src/examples/input1.txt --guard 18 --cell-size 10`
+        assert_eq!(room_svg(&room, &guards, &options), include_str!("testdata/input1_guard18.svg"));
+    }
+}
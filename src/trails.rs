@@ -0,0 +1,59 @@
+use serde::Serialize;
+use crate::types::*;
+
+/// One step of one guard, flattened for notebooks and spreadsheets.
+#[derive(Debug, Serialize)]
+pub struct TrailRow {
+    pub guard_index: usize,
+    pub step: usize,
+    pub x: usize,
+    pub y: usize,
+    pub direction: String,
+    pub obstacle_x: Option<usize>,
+    pub obstacle_y: Option<usize>,
+    pub is_loop: bool,
+}
+
+pub const CSV_HEADER: &str = "guard_index,step,x,y,direction,obstacle_x,obstacle_y,is_loop";
+
+pub fn trail_rows(guards: &AllGuards) -> Vec<TrailRow> {
+    guards.iter().flat_map(|guard| {
+        guard.trail.iter().enumerate().map(|(step, (dir, (x, y)))| TrailRow {
+            guard_index: guard.display_index,
+            step,
+            x: *x,
+            y: *y,
+            direction: format!("{:?}", dir),
+            obstacle_x: guard.obstacle.map(|(ox, _)| ox),
+            obstacle_y: guard.obstacle.map(|(_, oy)| oy),
+            is_loop: guard.is_loop,
+        })
+    }).collect()
+}
+
+pub fn to_csv(rows: &[TrailRow]) -> String {
+    let mut out = String::from(CSV_HEADER);
+    out.push('\n');
+    for row in rows {
+        let opt = |v: Option<usize>| v.map(|v| v.to_string()).unwrap_or_default();
+        out.push_str(&format!("{},{},{},{},{},{},{},{}\n",
+            row.guard_index, row.step, row.x, row.y, row.direction,
+            opt(row.obstacle_x), opt(row.obstacle_y), row.is_loop));
+    }
+    out
+}
+
+pub fn to_json(rows: &[TrailRow]) -> String {
+    serde_json::to_string(rows).unwrap_or_default()
+}
+
+/// Text rendering of one guard's trail. The part 1 guard gets every looping obstacle marked,
+/// a part 2 guard only its own.
+pub fn path_text(room: &Room, guards: &AllGuards, guard_index: usize) -> Option<String> {
+    let guard = guards.iter().find(|g| g.display_index == guard_index)?;
+    let loop_obstacles: Vec<(usize,usize)> = match guard.obstacle {
+        Some(obstacle) => vec![obstacle],
+        None => guards.iter().filter(|g| g.is_loop).filter_map(|g| g.obstacle).collect(),
+    };
+    Some(room.to_path_string(&guard.trail, &loop_obstacles))
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::io::{Write, stdout};
+use std::time::{Duration, Instant};
+
+use crossterm::{cursor, event, execute, queue, style, terminal};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::Stylize;
+
+use crate::types::*;
+
+const HELP: &str = "space pause  . step  , back  +/- speed  n/p next/prev loop  r restart  q quit";
+
+#[derive(Debug, Clone, Copy)]
+pub struct TuiOptions {
+    /// Seconds per step at speed 1, the same as the default `MoveTimer`.
+    pub tick: f32,
+}
+
+impl Default for TuiOptions {
+    fn default() -> TuiOptions {
+        TuiOptions{tick: 0.05}
+    }
+}
+
+/// The guard being played back, and how far along it is.
+struct Playback<'a> {
+    /// Part 1 guard first, then every looping part 2 guard.
+    guards: Vec<&'a Guard>,
+    current: usize,
+    step: usize,
+    paused: bool,
+    speed: f32,
+    /// First step each space was reached in, for the current guard.
+    first_visit: HashMap<(usize, usize), usize>,
+}
+
+impl<'a> Playback<'a> {
+    fn new(guards: &'a AllGuards) -> Playback<'a> {
+        let mut playback = Playback {
+            guards: guards.iter().filter(|g| g.display_index == 0 || g.makes_loop()).collect(),
+            current: 0,
+            step: 0,
+            paused: false,
+            speed: 1.,
+            first_visit: HashMap::new(),
+        };
+        playback.select(0);
+        playback
+    }
+    fn guard(&self) -> Option<&'a Guard> {
+        self.guards.get(self.current).copied()
+    }
+    fn select(&mut self, idx: usize) {
+        self.current = idx;
+        self.step = 0;
+        self.first_visit.clear();
+        if let Some(guard) = self.guard() {
+            for (step, (_, pos)) in guard.trail.iter().enumerate() {
+                self.first_visit.entry(*pos).or_insert(step);
+            }
+        }
+    }
+    fn cycle(&mut self, forward: bool) {
+        let len = self.guards.len().max(1);
+        let next = if forward { (self.current + 1) % len } else { (self.current + len - 1) % len };
+        self.select(next);
+    }
+    fn last_step(&self) -> usize {
+        self.guard().map(|g| g.trail.len().saturating_sub(1)).unwrap_or(0)
+    }
+    fn advance(&mut self) {
+        self.step = (self.step + 1).min(self.last_step());
+    }
+    fn retreat(&mut self) {
+        self.step = self.step.saturating_sub(1);
+    }
+}
+
+fn to_term_color(color: bevy::color::Color) -> style::Color {
+    let [r, g, b, _] = bevy::color::ColorToPacked::to_u8_array(color.to_srgba());
+    style::Color::Rgb{r, g, b}
+}
+
+/// Plays the guard walks back in the terminal until q or Esc is pressed.
+pub fn run_tui(room: &Room, guards: &AllGuards, options: &TuiOptions) -> Result<(), String> {
+    let mut out = stdout();
+    terminal::enable_raw_mode().map_err(|e| e.to_string())?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide).map_err(|e| e.to_string())?;
+    let result = play(room, guards, options);
+    // always give the terminal back, even when playback failed
+    let _ = execute!(out, cursor::Show, terminal::LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+    result
+}
+
+fn play(room: &Room, guards: &AllGuards, options: &TuiOptions) -> Result<(), String> {
+    let mut playback = Playback::new(guards);
+    let mut last_tick = Instant::now();
+    loop {
+        draw(room, &playback).map_err(|e| e.to_string())?;
+        let tick = Duration::from_secs_f32((options.tick / playback.speed).max(0.001));
+        let timeout = tick.saturating_sub(last_tick.elapsed());
+        if event::poll(timeout).map_err(|e| e.to_string())? {
+            let Event::Key(key) = event::read().map_err(|e| e.to_string())? else { continue; };
+            if key.kind != KeyEventKind::Press { continue; }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                KeyCode::Char(' ') => playback.paused = !playback.paused,
+                KeyCode::Char('.') | KeyCode::Right => {
+                    playback.paused = true;
+                    playback.advance();
+                }
+                KeyCode::Char(',') | KeyCode::Left => {
+                    playback.paused = true;
+                    playback.retreat();
+                }
+                KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Up => playback.speed = (playback.speed * 2.).min(64.),
+                KeyCode::Char('-') | KeyCode::Down => playback.speed = (playback.speed / 2.).max(1. / 16.),
+                KeyCode::Char('n') | KeyCode::Tab => playback.cycle(true),
+                KeyCode::Char('p') | KeyCode::BackTab => playback.cycle(false),
+                KeyCode::Char('r') => playback.step = 0,
+                _ => {}
+            }
+        } else {
+            last_tick = Instant::now();
+            if !playback.paused {
+                playback.advance();
+            }
+        }
+    }
+}
+
+fn draw(room: &Room, playback: &Playback) -> std::io::Result<()> {
+    let mut out = stdout();
+    let (cols, rows) = terminal::size()?;
+    let width = room.len();
+    let height = room.height();
+    // two lines at the bottom for the status and the keys
+    let (view_w, view_h) = (cols as usize, (rows as usize).saturating_sub(2));
+    let guard = playback.guard();
+    let (dir, (gx, gy)) = guard.and_then(|g| g.trail.get(playback.step).cloned())
+        .unwrap_or((Direction::Up, (0, 0)));
+    // keep the guard in the middle of the view when the room doesn't fit
+    let left = gx.saturating_sub(view_w / 2).min(width.saturating_sub(view_w));
+    let top = gy.saturating_sub(view_h / 2).min(height.saturating_sub(view_h));
+    let color = to_term_color(color_from_idx(guard.map(|g| g.display_index).unwrap_or(0)));
+    queue!(out, cursor::MoveTo(0, 0))?;
+    for y in top..(top + view_h).min(height) {
+        for x in left..(left + view_w).min(width) {
+            let visited = playback.first_visit.get(&(x, y)).is_some_and(|s| *s <= playback.step);
+            let cell = if guard.is_some() && (x, y) == (gx, gy) {
+                RoomSpace::Guard(dir.clone()).to_string().white().bold()
+            } else if guard.and_then(|g| g.obstacle) == Some((x, y)) {
+                "X".to_string().with(color).bold()
+            } else if room[x][y] == RoomSpace::Obstacle {
+                "#".to_string().dark_grey()
+            } else if visited {
+                "o".to_string().with(color)
+            } else {
+                ".".to_string().grey()
+            };
+            queue!(out, style::PrintStyledContent(cell))?;
+        }
+        queue!(out, terminal::Clear(terminal::ClearType::UntilNewLine), cursor::MoveToNextLine(1))?;
+    }
+    queue!(out, terminal::Clear(terminal::ClearType::FromCursorDown))?;
+    let status = match guard {
+        Some(g) => format!("{} | guard {} {}{} | step {}/{} | speed x{} {}",
+            room.label(),
+            g.display_index,
+            g.obstacle.map(|(x, y)| format!("obstacle {},{} ", x, y)).unwrap_or_default(),
+            if g.is_loop { "loops" } else { "exits" },
+            playback.step,
+            playback.last_step(),
+            playback.speed,
+            if playback.paused { "| paused" } else { "" }),
+        None => format!("{} | no guard", room.label()),
+    };
+    queue!(out, cursor::MoveTo(0, rows.saturating_sub(2)), style::Print(status.with(color)),
+        cursor::MoveTo(0, rows.saturating_sub(1)), style::Print(HELP.dark_grey()))?;
+    out.flush()
+}
@@ -6,6 +6,7 @@ use bevy::{
     asset::*,
     tasks::Task,
 };
+use crate::bundle::RoomSection;
 
 pub const CELL_SIZE: f32 = 20.0; // Define cell size in pixels
 pub const SCALE_FACTOR: f32 = 1.0; // Scaling factor for cell size
@@ -26,6 +27,9 @@ pub struct Obstacle(pub usize);
 
 #[derive(Component)]
 pub struct ComputeTrails(pub Task<CommandQueue>);
+/// A room being made by the Generate button.
+#[derive(Component)]
+pub struct GenerateRoom(pub Task<CommandQueue>);
 
 #[derive(Component)]
 pub struct ProgressBarFill;
@@ -40,11 +44,20 @@ pub struct LoopBoard(pub u64);
 pub struct ErrorBox(pub String);
 
 #[derive(Component)]
-pub struct InputText(pub String);
+pub struct InputText(pub Vec<RoomSection>);
 #[derive(Resource)]
 pub struct PendingText(pub String);
 #[derive(Resource)]
 pub struct CurrentError(pub String);
+/// File name and contents shown in the export window.
+#[derive(Resource)]
+pub struct PendingExport(pub Option<(String,String)>);
+/// Name of the `GlyphMap` used for input without a `glyphs=` header, and for Edit.
+#[derive(Resource)]
+pub struct CurrentGlyphs(pub String);
+/// Settings for the Generate button on the input screen.
+#[derive(Resource)]
+pub struct GeneratorSettings(pub crate::generator::GeneratorOptions);
 
 #[derive(Component)]
 pub struct MenuParent;
@@ -54,16 +67,23 @@ pub struct StateInfo{
     pub examples_loaded: bool,
     pub room_idx: Option<usize>,
     pub camera_target: usize,
+    pub compact_edit: bool,
 }
 impl StateInfo {
     pub fn new() -> StateInfo {
-        StateInfo{camera_target:0,room_idx:None,examples_loaded:false}
+        StateInfo{camera_target:0,room_idx:None,examples_loaded:false,compact_edit:false}
     }
     pub fn p2_loaded(room:&Room,guards:&AllGuards) -> bool {
         room.to_check.len() <= guards.0.len()
     }
 }
 
+impl Default for StateInfo {
+    fn default() -> StateInfo {
+        StateInfo::new()
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Hash, Eq)]
 pub enum Direction {
     Up,
@@ -87,28 +107,53 @@ pub struct Room {
     pub to_check: Vec<(usize,usize)>,
     pub visited: usize,
     pub index: usize,
+    pub name: Option<String>,
+    pub source: Option<String>,
+    pub notes: String,
+    pub expected_part1: Option<usize>,
+    pub expected_part2: Option<usize>,
 }
 
+/// First word of the compact format, e.g. `size 130x130`
+pub const SPARSE_HEADER: &str = "size";
+/// Largest width or height the compact format accepts, so a typo can't allocate the whole memory.
+pub const MAX_SPARSE_SIDE: usize = 2000;
+
+#[derive(Debug, PartialEq)]
 pub enum RoomError {
     NoGuards,
     ManyGuards,
     Uneven,
+    Malformed(String),
+    OutOfBounds((usize,usize)),
+    /// The guard and an obstacle were both put on this space.
+    GuardOnObstacle((usize,usize)),
 }
 impl Display for RoomError {
     fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
-        fmt.write_str(match self {
-            RoomError::NoGuards => "Room has no guards.",
-            RoomError::ManyGuards => "Room has more than one guard.",
-            RoomError::Uneven => "Room is not rectangular.",
-        })
+        match self {
+            RoomError::NoGuards => fmt.write_str("Room has no guards."),
+            RoomError::ManyGuards => fmt.write_str("Room has more than one guard."),
+            RoomError::Uneven => fmt.write_str("Room is not rectangular."),
+            RoomError::Malformed(token) => write!(fmt, "Could not parse \"{}\".", token),
+            RoomError::OutOfBounds((x,y)) => write!(fmt, "Position {},{} is outside the room.", x, y),
+            RoomError::GuardOnObstacle((x,y)) => write!(fmt, "Position {},{} has both the guard and an obstacle.", x, y),
+        }
     }
 }
 
 impl Room {
     pub fn new() -> Room {
-        Room{to_check: Vec::new(), grid: Vec::new(), visited:0, initial_guard_pos:None, index:0}
+        Room{to_check: Vec::new(), grid: Vec::new(), visited:0, initial_guard_pos:None, index:0, name:None, source:None, notes:String::new(), expected_part1:None, expected_part2:None}
     }
     pub fn from_string(input: String) -> Result<Room,RoomError> {
+        Room::from_string_with(input, &GlyphMap::default())
+    }
+    pub fn from_string_with(input: String, glyphs: &GlyphMap) -> Result<Room,RoomError> {
+        let first_line = input.lines().find(|l|!l.trim().is_empty()).unwrap_or("");
+        if first_line.split_whitespace().next() == Some(SPARSE_HEADER) {
+            return Room::from_sparse(&input);
+        }
         let mut rawout:Vec<Vec<RoomSpace>> = Vec::new();
 
         let mut guard_found = false;
@@ -116,14 +161,7 @@ impl Room {
         for line in input.lines() {
             let mut row:Vec<RoomSpace> = Vec::new();
             for c in line.chars() {
-                let value = match c {
-                    '^' => RoomSpace::Guard(Direction::Up),
-                    '<' => RoomSpace::Guard(Direction::Left),
-                    '>' => RoomSpace::Guard(Direction::Right),
-                    'v' => RoomSpace::Guard(Direction::Down),
-                    '#' => RoomSpace::Obstacle,
-                    _ => RoomSpace::Empty,
-                };
+                let value = glyphs.space(c);
                 if let RoomSpace::Guard(_) = value {
                     if guard_found {
                         return Err(RoomError::ManyGuards);
@@ -158,6 +196,80 @@ impl Room {
         newroom.initial_guard_pos = newroom.find_guard();
         Ok(newroom)
     }
+    /// Parses the compact format:
+    /// ```text
+    /// size 10x10
+    /// guard 4,6 ^
+    /// 4,0 9,1 2,3 7,4 1,6 8,7 0,8 6,9
+    /// ```
+    /// Every line after the header that is not a guard line is a list of obstacle coordinates.
+    fn from_sparse(input: &str) -> Result<Room,RoomError> {
+        let mut lines = input.lines().filter(|l|!l.trim().is_empty());
+        let header = lines.next().unwrap_or("");
+        let size = header.split_whitespace().nth(1).unwrap_or("");
+        let (width, height) = size.split_once('x')
+            .and_then(|(w,h)| Some((w.parse::<usize>().ok()?, h.parse::<usize>().ok()?)))
+            .filter(|(w,h)| (1..=MAX_SPARSE_SIDE).contains(w) && (1..=MAX_SPARSE_SIDE).contains(h))
+            .ok_or_else(|| RoomError::Malformed(header.to_string()))?;
+        let mut newroom = Room::new();
+        newroom.grid = vec![vec![RoomSpace::Empty; height]; width];
+        let parse_pos = |token: &str| -> Result<(usize,usize),RoomError> {
+            let pos = token.split_once(',')
+                .and_then(|(x,y)| Some((x.trim().parse::<usize>().ok()?, y.trim().parse::<usize>().ok()?)))
+                .ok_or_else(|| RoomError::Malformed(token.to_string()))?;
+            if pos.0 >= width || pos.1 >= height {
+                return Err(RoomError::OutOfBounds(pos));
+            }
+            Ok(pos)
+        };
+        let mut guard_found = false;
+        for line in lines {
+            let mut tokens = line.split_whitespace();
+            if line.trim_start().starts_with("guard") {
+                tokens.next();
+                if guard_found {
+                    return Err(RoomError::ManyGuards);
+                }
+                let (x,y) = parse_pos(tokens.next().unwrap_or(""))?;
+                let dir = match tokens.next() {
+                    Some("^") => Direction::Up,
+                    Some("<") => Direction::Left,
+                    Some(">") => Direction::Right,
+                    Some("v") => Direction::Down,
+                    _ => return Err(RoomError::Malformed(line.to_string())),
+                };
+                if newroom[x][y] == RoomSpace::Obstacle {
+                    return Err(RoomError::GuardOnObstacle((x,y)));
+                }
+                newroom.add_guard(x,y,&dir);
+                guard_found = true;
+            } else {
+                for token in tokens {
+                    let (x,y) = parse_pos(token)?;
+                    if let RoomSpace::Guard(_) = newroom[x][y] {
+                        return Err(RoomError::GuardOnObstacle((x,y)));
+                    }
+                    newroom.add_obstacle(x,y);
+                }
+            }
+        }
+        if !guard_found {
+            return Err(RoomError::NoGuards);
+        }
+        newroom.initial_guard_pos = newroom.find_guard();
+        Ok(newroom)
+    }
+    /// Number of rows, 0 for an empty room.
+    pub fn height(&self) -> usize {
+        self.first().map(|col| col.len()).unwrap_or(0)
+    }
+    /// Shown in the room list, falls back to the index for unnamed rooms.
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => self.index.to_string(),
+        }
+    }
     pub fn reset(&mut self) {
         // Iterate through the grid and reset RoomSpace values
         for row in &mut self.grid {
@@ -182,6 +294,9 @@ impl Room {
     pub fn visit_space(&mut self, x:usize, y:usize) {
         self[x][y] = RoomSpace::Visited;
     }
+    pub fn initial_guard(&self) -> Option<(Direction,(usize,usize))> {
+        self.initial_guard_pos.clone()
+    }
     pub fn find_guard(&self) -> Option<(Direction,(usize,usize))> {
         for (i, _) in self.iter().enumerate() {
             for (j, item) in self[i].iter().enumerate() {
@@ -197,6 +312,12 @@ impl Room {
     }
 }
 
+impl Default for Room {
+    fn default() -> Room {
+        Room::new()
+    }
+}
+
 impl Deref for Room {
     type Target = Vec<Vec<RoomSpace>>;
 
@@ -211,35 +332,143 @@ impl DerefMut for Room {
     }
 }
 
+/// `{:#}` emits the compact format read by `Room::from_string`
 impl Display for Room {
     fn fmt(&self, fmt:&mut Formatter) -> std::fmt::Result {
+        if fmt.alternate() {
+            return self.fmt_sparse(fmt);
+        }
+        fmt.write_str(&self.to_string_with(&GlyphMap::default()))
+    }
+}
+
+impl Room {
+    pub fn to_string_with(&self, glyphs: &GlyphMap) -> String {
         let mut resultstr = String::new();
         let num_cols = self.len();
         let num_rows = self[0].len();
         for col in 0..num_rows {
             let row: String = (0..num_cols)
-                .map(|row| self[row][col].to_string())
+                .map(|row| self[row][col].glyph(glyphs))
                 .collect();
                 resultstr.push_str(&row);
                 resultstr.push('\n');
         }
-        fmt.write_str(&resultstr)
+        resultstr
+    }
+    /// The puzzle statement's notation: `|` and `-` where the trail moved vertically or
+    /// horizontally, `+` where it did both, and `O` on the given obstacle candidates.
+    pub fn to_path_string(&self, trail: &Trail, loop_obstacles: &[(usize,usize)]) -> String {
+        let mut cells: Vec<Vec<char>> = (0..self[0].len())
+            .map(|y| (0..self.len()).map(|x| self[x][y].glyph(&GlyphMap::default())).collect())
+            .collect();
+        let mut moves = vec![vec![(false,false); self[0].len()]; self.len()];
+        for (dir,(x,y)) in trail.iter() {
+            match dir {
+                Direction::Up | Direction::Down => moves[*x][*y].0 = true,
+                Direction::Left | Direction::Right => moves[*x][*y].1 = true,
+            }
+        }
+        for (x, col) in moves.iter().enumerate() {
+            for (y, (vertical, horizontal)) in col.iter().enumerate() {
+                if let RoomSpace::Guard(_) = self[x][y] { continue; }
+                cells[y][x] = match (vertical, horizontal) {
+                    (true, true) => '+',
+                    (true, false) => '|',
+                    (false, true) => '-',
+                    (false, false) => cells[y][x],
+                };
+            }
+        }
+        for (x,y) in loop_obstacles {
+            cells[*y][*x] = 'O';
+        }
+        cells.into_iter().map(|row| row.into_iter().collect::<String>() + "\n").collect()
+    }
+    fn fmt_sparse(&self, fmt:&mut Formatter) -> std::fmt::Result {
+        writeln!(fmt, "{} {}x{}", SPARSE_HEADER, self.len(), self[0].len())?;
+        if let Some((dir,(x,y))) = self.find_guard() {
+            writeln!(fmt, "guard {},{} {}", x, y, RoomSpace::Guard(dir))?;
+        }
+        let obstacles: Vec<String> = (0..self[0].len())
+            .flat_map(|y| (0..self.len()).map(move |x| (x,y)))
+            .filter(|(x,y)| self[*x][*y] == RoomSpace::Obstacle)
+            .map(|(x,y)| format!("{},{}", x, y))
+            .collect();
+        for chunk in obstacles.chunks(16) {
+            writeln!(fmt, "{}", chunk.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl RoomSpace {
+    pub fn glyph(&self, glyphs: &GlyphMap) -> char {
+        match self {
+            RoomSpace::Guard(dir) => glyphs.guards.iter()
+                .find(|(_,d)| d == dir)
+                .map(|(c,_)| *c)
+                .unwrap_or(match dir {
+                    Direction::Up => '^',
+                    Direction::Down => 'v',
+                    Direction::Left => '<',
+                    Direction::Right => '>',
+                }),
+            RoomSpace::Obstacle => glyphs.obstacles.first().copied().unwrap_or('#'),
+            RoomSpace::Visited => glyphs.floor,
+            RoomSpace::Empty => glyphs.floor,
+        }
     }
 }
 
 impl Display for RoomSpace {
     fn fmt(&self, fmt:&mut Formatter) -> std::fmt::Result {
-        fmt.write_str(match self {
-            RoomSpace::Guard(dir) => match dir {
-                Direction::Up => "^",
-                Direction::Down => "v",
-                Direction::Left => "<",
-                Direction::Right => ">",
-            },
-            RoomSpace::Obstacle => "#",
-            RoomSpace::Visited => ".",
-            RoomSpace::Empty => ".",
-        })
+        write!(fmt, "{}", self.glyph(&GlyphMap::default()))
+    }
+}
+
+/// Which characters mean what in the plain room format.
+/// The first glyph listed for a direction or for obstacles is the one written out.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GlyphMap {
+    pub name: &'static str,
+    pub guards: Vec<(char, Direction)>,
+    pub obstacles: Vec<char>,
+    pub floor: char,
+}
+
+impl GlyphMap {
+    pub const PRESETS: [&'static str; 3] = ["aoc", "ascii", "blocks"];
+    pub fn from_name(name: &str) -> Option<GlyphMap> {
+        let arrows = vec![
+            ('^', Direction::Up),
+            ('>', Direction::Right),
+            ('v', Direction::Down),
+            ('<', Direction::Left),
+        ];
+        let mut with_at = arrows.clone();
+        with_at.insert(0, ('@', Direction::Up));
+        match name {
+            "aoc" => Some(GlyphMap{name:"aoc", guards:arrows, obstacles:vec!['#'], floor:'.'}),
+            "ascii" => Some(GlyphMap{name:"ascii", guards:with_at, obstacles:vec!['X','#','█'], floor:'.'}),
+            "blocks" => Some(GlyphMap{name:"blocks", guards:with_at, obstacles:vec!['█','X','#'], floor:'.'}),
+            _ => None,
+        }
+    }
+    pub fn space(&self, c: char) -> RoomSpace {
+        if self.obstacles.contains(&c) {
+            RoomSpace::Obstacle
+        } else if let Some((_,dir)) = self.guards.iter().find(|(g,_)| *g == c) {
+            RoomSpace::Guard(dir.clone())
+        } else {
+            RoomSpace::Empty
+        }
+    }
+}
+
+impl Default for GlyphMap {
+    fn default() -> GlyphMap {
+        GlyphMap::from_name("aoc").unwrap()
     }
 }
 
@@ -252,6 +481,12 @@ impl Trail {
     }
 }
 
+impl Default for Trail {
+    fn default() -> Trail {
+        Trail::new()
+    }
+}
+
 impl Deref for Trail {
     type Target = Vec<(Direction,(usize,usize))>;
 
@@ -274,7 +509,21 @@ impl AllGuards {
         AllGuards(Vec::new())
     }
     pub fn sort_by_idx(&mut self) {
-        self.0.sort_by(|a, b| a.display_index.cmp(&b.display_index));
+        self.0.sort_by_key(|a| a.display_index);
+    }
+    /// The part 2 answer: obstacles that trap the guard in a loop.
+    pub fn loop_count(&self) -> usize {
+        self.iter().filter(|g| g.makes_loop()).count()
+    }
+    /// Display indices of the guards counted by `loop_count`.
+    pub fn loop_indices(&self) -> Vec<usize> {
+        self.iter().filter(|g| g.makes_loop()).map(|g| g.display_index).collect()
+    }
+}
+
+impl Default for AllGuards {
+    fn default() -> AllGuards {
+        AllGuards::new()
     }
 }
 
@@ -310,6 +559,44 @@ pub struct Space {
 #[derive(Resource)]
 pub struct MoveTimer(pub Timer);
 
+/// Whether the camera follows `StateInfo::camera_target` or is moved by hand.
+#[derive(Resource)]
+pub struct CameraControl {
+    pub follow: bool,
+    /// Zoom out to show the whole room on the next frame.
+    pub fit: bool,
+}
+
+impl Default for CameraControl {
+    fn default() -> CameraControl {
+        CameraControl{follow: true, fit: false}
+    }
+}
+
+/// Side by side view of two part 2 guards. The left one is `StateInfo::camera_target`.
+#[derive(Resource, Default)]
+pub struct Comparison {
+    pub enabled: bool,
+    /// Guard followed by the right viewport.
+    pub guard: usize,
+}
+
+/// The camera drawing the right half of the window while comparing.
+#[derive(Component)]
+pub struct CompareCamera;
+
+/// Marks where a compared guard's walk leaves the part 1 path.
+#[derive(Component)]
+pub struct DivergenceMarker(pub usize);
+
+/// Pause state and timeline requests from the playback controls.
+#[derive(Resource, Default)]
+pub struct Playback {
+    pub paused: bool,
+    /// Trail index every guard should jump to on the next frame.
+    pub seek: Option<usize>,
+}
+
 #[derive(Component, Debug, PartialEq, Hash, Eq, Clone)]
 pub struct Guard {
     pub trail: Trail,
@@ -324,9 +611,14 @@ impl Guard {
     pub fn new(trail: Trail, obstacle: Option<(usize,usize)>, is_loop: bool, display_index: usize) -> Guard {
         Guard { trail, obstacle, is_loop, trail_idx: 0, display_index,counted: false}
     }
+    /// A part 2 guard whose extra obstacle makes it loop.
+    pub fn makes_loop(&self) -> bool {
+        self.is_loop && self.obstacle.is_some()
+    }
     pub fn retreat(&mut self) -> Option<(Direction,(usize,usize))> {
-        if self.trail.get(self.trail_idx).is_some() && self.trail_idx > 0 {
-            self.trail_idx -= 1;
+        // advance leaves trail_idx one past the end once the walk is over
+        if self.trail_idx > 0 && !self.trail.is_empty() {
+            self.trail_idx = self.trail_idx.min(self.trail.len()) - 1;
             let pos = self.trail[self.trail_idx].clone();
             Some(pos)
         } else { None }
@@ -349,11 +641,7 @@ impl Guard {
         if let Some((dir,(x,y))) = self.trail.get(self.trail_idx) {
             Some((dir.clone(),(*x,*y)))
         } else if self.trail_idx >= self.trail.len() {
-            if let Some(ret) = self.trail.last() {
-                Some(ret.clone())
-            } else {
-                None
-            }
+            self.trail.last().cloned()
         } else {
             None
         }
@@ -361,6 +649,16 @@ impl Guard {
     pub fn reset(&mut self) {
         self.trail_idx = 0;
     }
+    /// Index in the trail where the loop starts.
+    /// A looping trail ends on the first repeated step, so the cycle starts at its earlier occurrence.
+    pub fn cycle_start(&self) -> Option<usize> {
+        if !self.is_loop { return None; }
+        let last = self.trail.last()?;
+        self.trail.iter().position(|step| step == last).filter(|i| *i < self.trail.len() - 1)
+    }
+    pub fn cycle_len(&self) -> Option<usize> {
+        self.cycle_start().map(|start| self.trail.len() - 1 - start)
+    }
     pub fn get_sprite(&self) -> String {
         let index = self.display_index % 3;
         let direction = if let Some((d,_)) = self.get_loc() { d } else { Direction::Down };
@@ -401,6 +699,19 @@ impl TrailEntity {
     }
 }
 
+/// Trail sprite inside a loop's cycle, drawn pulsing.
+#[derive(Component)]
+pub struct CycleTrail;
+
+/// Marks the space where a guard's walk turns into a loop.
+#[derive(Component)]
+pub struct CycleEntry {
+    pub guard_index: usize,
+    pub pos: (usize, usize),
+    pub step: usize,
+    pub cycle_len: usize,
+}
+
 #[derive(Debug, Clone, Resource)]
 pub struct AllRooms(pub Vec<(Room,AllGuards)>);
 
@@ -413,16 +724,22 @@ impl AllRooms {
             for entry in &mut self.0 {
                 if entry.0.index == room_idx { return Some(entry); }
             };
-            return None;
-        } else { return None; }
+            None
+        } else { None }
     }
     pub fn get_room(&self,room_idx:Option<usize>) -> Option<&(Room,AllGuards)> {
         if let Some(room_idx) = room_idx {
             for entry in &self.0 {
                 if entry.0.index == room_idx { return Some(entry); }
             };
-            return None;
-        } else { return None; }
+            None
+        } else { None }
+    }
+}
+
+impl Default for AllRooms {
+    fn default() -> AllRooms {
+        AllRooms::new()
     }
 }
 
@@ -472,6 +789,7 @@ impl Plugin for EmbeddedPlug {
         embedded_asset!(app, "examples/input2.txt");
         embedded_asset!(app, "examples/input3.txt");
         embedded_asset!(app, "examples/input4.txt");
+        embedded_asset!(app, "examples/input5.png");
     }
 }
 
@@ -482,3 +800,105 @@ pub fn random_obstacle() -> String {
 pub fn color_from_idx(idx: usize) -> Color {
     Color::hsv((idx as f32 * 10. + 100.) % 360., 1., 1.)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("examples/input1.txt");
+    const EXAMPLE_SPARSE: &str = "\
+size 10x10
+guard 4,6 ^
+4,0 9,1 2,3 7,4 1,6 8,7 0,8 6,9
+";
+
+    fn sparse(input: &str) -> Result<Room,RoomError> {
+        Room::from_string(input.to_string())
+    }
+
+    #[test]
+    fn sparse_matches_plain() {
+        let room = sparse(EXAMPLE_SPARSE).unwrap();
+        assert_eq!(room, Room::from_string(EXAMPLE.to_string()).unwrap());
+        assert_eq!(room.initial_guard(), Some((Direction::Up,(4,6))));
+    }
+
+    #[test]
+    fn sparse_allows_blank_lines_and_split_obstacles() {
+        let room = sparse("\nsize 3x2\n\n0,0\n  guard 1,1 >\n2,0 2,1\n").unwrap();
+        assert_eq!(room.to_string(), "#.#\n.>#\n");
+    }
+
+    #[test]
+    fn alternate_format_round_trips() {
+        let room = Room::from_string(EXAMPLE.to_string()).unwrap();
+        let compact = format!("{:#}", room);
+        assert_eq!(compact, EXAMPLE_SPARSE);
+        assert_eq!(sparse(&compact).unwrap(), room);
+    }
+
+    #[test]
+    fn sparse_errors() {
+        assert_eq!(sparse("size 3x3\n0,0\n"), Err(RoomError::NoGuards));
+        assert_eq!(sparse("size 3x3\nguard 0,0 ^\nguard 1,1 ^\n"), Err(RoomError::ManyGuards));
+        assert_eq!(sparse("size 3by3\nguard 0,0 ^\n"), Err(RoomError::Malformed("size 3by3".to_string())));
+        assert_eq!(sparse("size 0x3\nguard 0,0 ^\n"), Err(RoomError::Malformed("size 0x3".to_string())));
+        assert_eq!(sparse("size 100000x100000\nguard 0,0 ^\n"), Err(RoomError::Malformed("size 100000x100000".to_string())));
+        assert!(sparse("size 2000x2000\nguard 0,0 ^\n").is_ok());
+        assert_eq!(sparse("size 3x3\nguard 0,0 up\n"), Err(RoomError::Malformed("guard 0,0 up".to_string())));
+        assert_eq!(sparse("size 3x3\nguard 0,0 ^\n1;1\n"), Err(RoomError::Malformed("1;1".to_string())));
+        assert_eq!(sparse("size 3x3\nguard 0,0 ^\n3,1\n"), Err(RoomError::OutOfBounds((3,1))));
+        assert_eq!(sparse("size 3x3\nguard 1,1 ^\n1,1\n"), Err(RoomError::GuardOnObstacle((1,1))));
+        assert_eq!(sparse("size 3x3\n1,1\nguard 1,1 ^\n"), Err(RoomError::GuardOnObstacle((1,1))));
+    }
+
+    #[test]
+    fn plain_errors() {
+        assert_eq!(Room::from_string("...\n.#.\n".to_string()), Err(RoomError::NoGuards));
+        assert_eq!(Room::from_string("^..\n..^\n".to_string()), Err(RoomError::ManyGuards));
+        assert_eq!(Room::from_string("^..\n..\n".to_string()), Err(RoomError::Uneven));
+    }
+
+    #[test]
+    fn glyph_presets_read_their_glyphs() {
+        for name in GlyphMap::PRESETS {
+            let glyphs = GlyphMap::from_name(name).unwrap();
+            assert_eq!(glyphs.space('.'), RoomSpace::Empty, "{}", name);
+            assert_eq!(glyphs.space('^'), RoomSpace::Guard(Direction::Up), "{}", name);
+            assert_eq!(glyphs.space('>'), RoomSpace::Guard(Direction::Right), "{}", name);
+            assert_eq!(glyphs.space('v'), RoomSpace::Guard(Direction::Down), "{}", name);
+            assert_eq!(glyphs.space('<'), RoomSpace::Guard(Direction::Left), "{}", name);
+            assert_eq!(glyphs.space('#'), RoomSpace::Obstacle, "{}", name);
+            // anything unknown is floor
+            assert_eq!(glyphs.space('?'), RoomSpace::Empty, "{}", name);
+        }
+        let aoc = GlyphMap::from_name("aoc").unwrap();
+        assert_eq!(aoc.space('@'), RoomSpace::Empty);
+        assert_eq!(aoc.space('X'), RoomSpace::Empty);
+        for name in ["ascii", "blocks"] {
+            let glyphs = GlyphMap::from_name(name).unwrap();
+            assert_eq!(glyphs.space('@'), RoomSpace::Guard(Direction::Up), "{}", name);
+            assert_eq!(glyphs.space('X'), RoomSpace::Obstacle, "{}", name);
+            assert_eq!(glyphs.space('█'), RoomSpace::Obstacle, "{}", name);
+        }
+        assert_eq!(GlyphMap::from_name("emoji"), None);
+    }
+
+    #[test]
+    fn glyph_presets_write_their_glyphs() {
+        let room = Room::from_string("#..\n.>.\n..#\n".to_string()).unwrap();
+        for (name, expected) in [
+            ("aoc", "#..\n.>.\n..#\n"),
+            ("ascii", "X..\n.>.\n..X\n"),
+            ("blocks", "█..\n.>.\n..█\n"),
+        ] {
+            let glyphs = GlyphMap::from_name(name).unwrap();
+            let text = room.to_string_with(&glyphs);
+            assert_eq!(text, expected, "{}", name);
+            assert_eq!(Room::from_string_with(text, &glyphs).unwrap(), room, "{}", name);
+        }
+        // the up guard is written as '@' where that's the first glyph listed
+        let up = Room::from_string(".^.".to_string()).unwrap();
+        assert_eq!(up.to_string_with(&GlyphMap::from_name("ascii").unwrap()), ".@.\n");
+    }
+}
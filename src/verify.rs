@@ -0,0 +1,190 @@
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer};
+
+/// One `[[room]]` table of an expectations file.
+/// ```toml
+/// [[room]]
+/// path = "src/examples/input1.txt"
+/// room = "Example 1"  # index or name inside a bundle, the first room if left out
+/// part1 = 41
+/// part2 = 6
+/// ```
+/// Answers that are left out fall back to the `part1=` and `part2=` of the bundle header.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectation {
+    pub path: String,
+    #[serde(default, deserialize_with = "room_key")]
+    pub room: Option<String>,
+    pub part1: Option<usize>,
+    pub part2: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExpectationsFile {
+    #[serde(default)]
+    room: Vec<Expectation>,
+}
+
+/// `room = 2` and `room = "2"` both pick the third room.
+fn room_key<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Key {
+        Index(usize),
+        Name(String),
+    }
+    Ok(Some(match Key::deserialize(deserializer)? {
+        Key::Index(i) => i.to_string(),
+        Key::Name(name) => name,
+    }))
+}
+
+pub fn parse_expectations(text: &str) -> Result<Vec<Expectation>, String> {
+    let file: ExpectationsFile = toml::from_str(text).map_err(|e| e.to_string())?;
+    Ok(file.room)
+}
+
+/// What the solver said about one expectation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub label: String,
+    pub expected: (Option<usize>, Option<usize>),
+    pub actual: Option<(usize, usize)>,
+    pub error: Option<String>,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        if self.error.is_some() { return false; }
+        let Some((part1, part2)) = self.actual else { return false; };
+        self.expected.0.is_none_or(|e| e == part1) && self.expected.1.is_none_or(|e| e == part2)
+    }
+    /// One line per expectation, with the differences spelled out on failure.
+    pub fn describe(&self) -> String {
+        if let Some(err) = &self.error {
+            return format!("ERROR {}: {}", self.label, err);
+        }
+        let Some((part1, part2)) = self.actual else {
+            return format!("ERROR {}: not solved", self.label);
+        };
+        if self.passed() {
+            return format!("ok    {}: part 1 {}, part 2 {}", self.label, part1, part2);
+        }
+        let mut diffs = Vec::new();
+        if let Some(expected) = self.expected.0.filter(|e| *e != part1) {
+            diffs.push(format!("part 1 expected {} got {}", expected, part1));
+        }
+        if let Some(expected) = self.expected.1.filter(|e| *e != part2) {
+            diffs.push(format!("part 2 expected {} got {}", expected, part2));
+        }
+        format!("FAIL  {}: {}", self.label, diffs.join(", "))
+    }
+}
+
+/// Solves the room an expectation points at. Paths are relative to `base`, the expectations file's folder.
+pub fn check(expectation: &Expectation, base: &Path) -> Outcome {
+    let path = base.join(&expectation.path);
+    let path = path.to_string_lossy().to_string();
+    let mut outcome = Outcome {
+        label: match &expectation.room {
+            Some(room) => format!("{} [{}]", expectation.path, room),
+            None => expectation.path.clone(),
+        },
+        expected: (expectation.part1, expectation.part2),
+        actual: None,
+        error: None,
+    };
+    let solved = crate::cli::read_sections(&path).and_then(|sections| {
+        let wanted = expectation.room.as_deref().unwrap_or("0");
+        let section = sections.iter().enumerate()
+            .find(|(i, s)| i.to_string() == wanted || s.name.as_deref() == Some(wanted))
+            .map(|(_, s)| s)
+            .ok_or_else(|| format!("no room {}", wanted))?;
+        crate::cli::solve_section(section)
+    });
+    match solved {
+        Ok((room, guards)) => {
+            outcome.expected.0 = outcome.expected.0.or(room.expected_part1);
+            outcome.expected.1 = outcome.expected.1.or(room.expected_part2);
+            if outcome.expected == (None, None) {
+                outcome.error = Some("no part1 or part2 to check against".to_string());
+            }
+            outcome.actual = Some((room.visited, guards.loop_count()));
+        }
+        Err(err) => outcome.error = Some(err),
+    }
+    outcome
+}
+
+pub fn check_all(expectations: &[Expectation], base: &Path) -> Vec<Outcome> {
+    expectations.iter().map(|e| check(e, base)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_rooms() {
+        let text = r#"
+# comment
+[[room]]
+path = "a.txt"
+part1 = 41
+part2 = 6
+
+[[room]]
+path = 'b.txt'   # single quotes are fine
+room = 2
+
+[[room]]
+path = "c.txt"
+room = "Example 1"
+part2 = 1_000
+"#;
+        assert_eq!(parse_expectations(text), Ok(vec![
+            Expectation{path: "a.txt".to_string(), room: None, part1: Some(41), part2: Some(6)},
+            Expectation{path: "b.txt".to_string(), room: Some("2".to_string()), part1: None, part2: None},
+            Expectation{path: "c.txt".to_string(), room: Some("Example 1".to_string()), part1: None, part2: Some(1000)},
+        ]));
+    }
+
+    #[test]
+    fn reads_inline_tables() {
+        let text = r#"room = [{ path = "a.txt", part1 = 1 }]"#;
+        assert_eq!(parse_expectations(text), Ok(vec![
+            Expectation{path: "a.txt".to_string(), part1: Some(1), ..Default::default()},
+        ]));
+    }
+
+    #[test]
+    fn empty_file_has_no_rooms() {
+        assert_eq!(parse_expectations("# nothing yet\n"), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        for text in [
+            "[[room]]\npath = \"a.txt\"\nroom = Example 1\n",
+            "[[room]]\npart1 = 41\n",
+            "[[room]]\npath = \"a.txt\"\npart1 = \"41\"\n",
+            "[[room]]\npath = \"a.txt\"\npart3 = 1\n",
+            "path = \"a.txt\"\n",
+        ] {
+            assert!(parse_expectations(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn shipped_expectations_pass() {
+        let text = include_str!("../expectations.toml");
+        let outcomes = check_all(&parse_expectations(text).unwrap(), Path::new(env!("CARGO_MANIFEST_DIR")));
+        assert_eq!(outcomes.len(), 5);
+        for outcome in outcomes {
+            assert!(outcome.passed(), "{}", outcome.describe());
+        }
+    }
+}